@@ -0,0 +1,234 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// The public GitHub host; every other host is treated as a GitHub Enterprise Server
+pub const GITHUB_COM: &str = "github.com";
+
+/// Where a GitHub token was found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TokenSource {
+    /// An environment variable, e.g. `GITHUB_TOKEN`
+    Env(&'static str),
+    /// The `gh` CLI (`hosts.yml`, or its keyring entry through `gh auth token`)
+    GhCli,
+    /// `git credential fill` for the host
+    GitCredential,
+    /// `token` in the host's config section
+    Config,
+}
+
+impl fmt::Display for TokenSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenSource::Env(name) => f.write_str(name),
+            TokenSource::GhCli => f.write_str("gh CLI"),
+            TokenSource::GitCredential => f.write_str("git credential"),
+            TokenSource::Config => f.write_str("config"),
+        }
+    }
+}
+
+/// A token and where it came from
+#[derive(Clone, PartialEq)]
+pub struct Token {
+    pub value: String,
+    pub source: TokenSource,
+}
+
+// Keeps the token itself out of logs and panics
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token(from {})", self.source)
+    }
+}
+
+/// Looks for a token for `host`, in order: environment variables, the `gh` CLI,
+/// git credential helpers, then the configured token
+pub fn discover_token(host: &str, configured: Option<&str>) -> Option<Token> {
+    env_token(host, |name| std::env::var(name).ok())
+        .or_else(|| gh_cli_token(host))
+        .or_else(|| git_credential_token(host))
+        .or_else(|| {
+            configured.map(|value| Token {
+                value: value.to_owned(),
+                source: TokenSource::Config,
+            })
+        })
+}
+
+/// Environment variables holding a token for `host`, most specific first
+/// (the same ones the `gh` CLI reads)
+fn token_env_vars(host: &str) -> &'static [&'static str] {
+    if host == GITHUB_COM {
+        &["GITHUB_TOKEN", "GH_TOKEN"]
+    } else {
+        &["GH_ENTERPRISE_TOKEN", "GITHUB_ENTERPRISE_TOKEN"]
+    }
+}
+
+fn env_token(host: &str, var: impl Fn(&str) -> Option<String>) -> Option<Token> {
+    token_env_vars(host).iter().find_map(|name| {
+        var(name)
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| Token {
+                value: value.trim().to_owned(),
+                source: TokenSource::Env(name),
+            })
+    })
+}
+
+/// Reads the token `gh auth login` stored for the host: in `hosts.yml`, or in the
+/// system keyring, which only `gh auth token` can read
+fn gh_cli_token(host: &str) -> Option<Token> {
+    let from_file = gh_hosts_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| gh_hosts_token(&contents, host));
+    let value = from_file.or_else(|| {
+        let output = Command::new("gh")
+            .args(["auth", "token", "--hostname", host])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        let token = String::from_utf8(output.stdout).ok()?.trim().to_owned();
+        (output.status.success() && !token.is_empty()).then_some(token)
+    })?;
+    Some(Token {
+        value,
+        source: TokenSource::GhCli,
+    })
+}
+
+/// `$GH_CONFIG_DIR/hosts.yml`, else `$XDG_CONFIG_HOME/gh/hosts.yml`, else `~/.config/gh/hosts.yml`
+fn gh_hosts_path() -> Option<PathBuf> {
+    let dir = std::env::var_os("GH_CONFIG_DIR")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("XDG_CONFIG_HOME").map(|dir| PathBuf::from(dir).join("gh")))
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config").join("gh")))?;
+    Some(dir.join("hosts.yml"))
+}
+
+#[derive(Debug, Deserialize)]
+struct GhHost {
+    oauth_token: Option<String>,
+}
+
+/// Extracts the host's `oauth_token` from the contents of `gh`'s `hosts.yml`
+fn gh_hosts_token(contents: &str, host: &str) -> Option<String> {
+    let hosts: HashMap<String, GhHost> = serde_yaml::from_str(contents).ok()?;
+    hosts
+        .into_iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(host))?
+        .1
+        .oauth_token
+        .filter(|token| !token.is_empty())
+}
+
+/// Asks git's credential helpers for the password stored for `https://<host>`
+/// Prompts are disabled so a missing credential never blocks the TUI
+fn git_credential_token(host: &str) -> Option<Token> {
+    let mut child = Command::new("git")
+        .args(["credential", "fill"])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_ASKPASS", "true")
+        .env("SSH_ASKPASS", "true")
+        .env("GCM_INTERACTIVE", "never")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    child
+        .stdin
+        .take()?
+        .write_all(format!("protocol=https\nhost={}\n\n", host).as_bytes())
+        .ok()?;
+    let output = child.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = credential_password(&String::from_utf8(output.stdout).ok()?)?;
+    Some(Token {
+        value,
+        source: TokenSource::GitCredential,
+    })
+}
+
+/// Reads `password=` from `git credential fill` output
+fn credential_password(output: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.strip_prefix("password="))
+        .map(|password| password.trim().to_owned())
+        .filter(|password| !password.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_vars_depend_on_the_host() {
+        let vars = |name: &str| match name {
+            "GH_TOKEN" => Some("gh-token".to_owned()),
+            "GITHUB_ENTERPRISE_TOKEN" => Some("ghe-token".to_owned()),
+            _ => None,
+        };
+
+        let github = env_token("github.com", vars).unwrap();
+        let enterprise = env_token("github.example.com", vars).unwrap();
+
+        assert_eq!(github.value, "gh-token");
+        assert_eq!(github.source, TokenSource::Env("GH_TOKEN"));
+        assert_eq!(enterprise.value, "ghe-token");
+        assert_eq!(enterprise.source.to_string(), "GITHUB_ENTERPRISE_TOKEN");
+        assert_eq!(env_token("github.com", |_| Some("  ".to_owned())), None);
+    }
+
+    #[test]
+    fn reads_gh_hosts_file() {
+        let contents = r#"
+github.com:
+    user: octocat
+    oauth_token: gho_public
+    git_protocol: https
+github.example.com:
+    user: octocat
+    git_protocol: ssh
+"#;
+
+        assert_eq!(
+            gh_hosts_token(contents, "github.com"),
+            Some("gho_public".to_owned())
+        );
+        // Token kept in the keyring: nothing in the file
+        assert_eq!(gh_hosts_token(contents, "github.example.com"), None);
+        assert_eq!(gh_hosts_token(contents, "gitlab.com"), None);
+        assert_eq!(gh_hosts_token("not: [valid", "github.com"), None);
+    }
+
+    #[test]
+    fn reads_password_from_credential_output() {
+        let output = "protocol=https\nhost=github.com\nusername=octocat\npassword=ghp_secret\n";
+
+        assert_eq!(credential_password(output), Some("ghp_secret".to_owned()));
+        assert_eq!(
+            credential_password("protocol=https\nhost=github.com\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn debug_output_hides_the_token() {
+        let token = Token {
+            value: "ghp_secret".to_owned(),
+            source: TokenSource::Config,
+        };
+
+        assert_eq!(format!("{:?}", token), "Token(from config)");
+    }
+}
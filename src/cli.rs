@@ -0,0 +1,576 @@
+use std::error::Error;
+use std::io::{self, BufRead, Write};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::config::Config;
+use crate::policy::{self, SelectionPolicy};
+#[cfg(any(test, feature = "github-api"))]
+use crate::store::BranchScope;
+use crate::store::{BranchStore, DeletionOutcome};
+use crate::tui;
+use crate::view_model::{BranchViewModel, DeletionReport, ViewState};
+use crate::{BCBranch, PrStatus};
+
+/// Command-line arguments
+/// Running without a subcommand launches the TUI
+#[derive(Debug, Parser)]
+#[command(
+    version,
+    about = "Clean up local git branches based on their GitHub PR status"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Report what would be deleted without touching any refs
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Also pre-select branches whose upstream branch was deleted
+    #[arg(long, global = true)]
+    select_gone: bool,
+
+    /// Also delete each branch on the remote (never the head of an open PR)
+    #[arg(long, global = true)]
+    delete_remote: bool,
+
+    /// Manage branches on GitHub instead of local ones (deleted through the GitHub API)
+    #[arg(long, global = true, conflicts_with = "delete_remote")]
+    remote_branches: bool,
+
+    /// Also pre-select branches without commits for this many days (and no open PR)
+    #[arg(long, global = true, value_name = "DAYS")]
+    stale_days: Option<u64>,
+
+    /// Also pre-select branches whose PR was closed without merging this many days ago
+    #[arg(long, global = true, value_name = "DAYS")]
+    closed_days: Option<u64>,
+
+    /// Selection policy replacing the other rule options, e.g. "merged or (gone and stale:90)"
+    /// Rules: merged, merged-locally, gone, closed[:DAYS], stale:DAYS, combined with and/or
+    #[arg(
+        long,
+        global = true,
+        value_name = "EXPR",
+        conflicts_with_all = ["select_gone", "stale_days", "closed_days"]
+    )]
+    policy: Option<String>,
+
+    /// Remote whose branches are managed (default: origin)
+    /// PRs are looked up on the `upstream` remote's repository when there is one
+    #[arg(long, global = true, value_name = "NAME")]
+    remote: Option<String>,
+
+    /// Maximum number of PR lookups in flight at once (default: 8)
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    concurrency: Option<u16>,
+}
+
+impl Cli {
+    /// Applies options that configure the store rather than a command
+    pub fn apply_to(&self, config: &mut Config) {
+        if let Some(concurrency) = self.concurrency {
+            config.github.concurrency = Some(concurrency.into());
+        }
+        if self.select_gone {
+            config.defaults.select_gone = Some(true);
+        }
+        if self.policy.is_some() {
+            config.select.policy = self.policy.clone();
+        }
+        if self.stale_days.is_some() {
+            config.select.stale_days = self.stale_days;
+        }
+        if self.closed_days.is_some() {
+            config.select.closed_days = self.closed_days;
+        }
+        if let Some(remote) = &self.remote {
+            config.defaults.remote = Some(remote.clone());
+        }
+    }
+
+    /// Which branches to manage: local ones, or the ones on the remote
+    #[cfg(any(test, feature = "github-api"))]
+    pub fn scope(&self) -> BranchScope {
+        if self.remote_branches {
+            BranchScope::Remote
+        } else {
+            BranchScope::Local
+        }
+    }
+
+    /// Returns the requested subcommand, defaulting to the TUI
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Tui)
+    }
+}
+
+/// Subcommands supported by the binary
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum Command {
+    /// Launch the interactive branch browser (default)
+    Tui,
+    /// Print every branch with its PR status
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Delete branches without the TUI
+    Clean {
+        /// Delete branches whose PR was merged (the TUI's default selection)
+        #[arg(long)]
+        merged: bool,
+        /// Delete branches whose upstream branch was deleted (and have no open PR)
+        #[arg(long)]
+        gone: bool,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Recreate the most recently deleted batch of branches
+    Restore,
+}
+
+/// Output formats for `list`
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// One human-readable line per branch
+    Text,
+    /// A single JSON array, written once every branch has loaded
+    Json,
+    /// One JSON object per line, streamed as each branch is enriched
+    Ndjson,
+}
+
+/// Runs the parsed command line against a store
+/// `update_rx` streams PR updates for branches that are still loading
+pub async fn run<T: BranchStore>(
+    cli: Cli,
+    config: &Config,
+    store: T,
+    update_rx: UnboundedReceiver<BCBranch>,
+) -> Result<(), Box<dyn Error>> {
+    let dry_run = cli.dry_run || config.defaults.dry_run.unwrap_or(false);
+    let selection = policy::configured(config)?;
+    let (stale_days, closed_days) = (cli.stale_days, cli.closed_days);
+    let policy_expr = cli.policy.clone();
+    let delete_remote = cli.delete_remote || config.defaults.delete_remote.unwrap_or(false);
+    let mut view_model = BranchViewModel::new(store)
+        .with_dry_run(dry_run)
+        .with_policy(selection)
+        .with_delete_remote(delete_remote);
+
+    match cli.command() {
+        Command::Tui => {
+            // Use slow animation for better readability
+            let animation_config = tui::AnimationConfig::slow();
+            tui::run_branch_tui(view_model, update_rx, animation_config).await
+        }
+        Command::List { format } => {
+            match format {
+                OutputFormat::Text => {
+                    let state = wait_for_branches(&view_model, update_rx).await;
+                    print_branches(&state.branches, &mut io::stdout().lock())?;
+                }
+                OutputFormat::Json => {
+                    let state = wait_for_branches(&view_model, update_rx).await;
+                    let mut out = io::stdout().lock();
+                    serde_json::to_writer_pretty(&mut out, &state.branches)?;
+                    writeln!(out)?;
+                }
+                OutputFormat::Ndjson => {
+                    stream_ndjson(&view_model, update_rx, &mut io::stdout()).await?;
+                }
+            }
+            Ok(())
+        }
+        Command::Clean { merged, gone, yes } => {
+            // Explicit flags decide what gets deleted, not the configured defaults
+            let has_rules = merged || gone || stale_days.is_some() || closed_days.is_some();
+            let selection: Box<dyn SelectionPolicy> = match policy_expr {
+                Some(_) if merged || gone => {
+                    return Err("--policy cannot be combined with --merged or --gone".into())
+                }
+                Some(expr) => policy::parse(&expr)?,
+                None if has_rules => {
+                    Box::new(policy::from_rules(merged, gone, stale_days, closed_days))
+                }
+                None => {
+                    return Err("nothing to clean: pass --merged, --gone, --stale-days, --closed-days or --policy".into())
+                }
+            };
+            view_model = view_model.with_policy(selection);
+
+            let mut state = wait_for_branches(&view_model, update_rx).await;
+            if state.selected_branches.is_empty() {
+                println!("No branches to delete.");
+                return Ok(());
+            }
+
+            if dry_run {
+                let report = view_model.delete_selected_branches(&mut state).await;
+                println!("Dry run: {} branch(es) would be deleted:", report.len());
+                print_deletion_report(&report, &mut io::stdout().lock())?;
+                return Ok(());
+            }
+
+            println!("Branches to delete:");
+            for name in &state.selected_branches {
+                println!("  {}", name);
+            }
+
+            if !yes && !confirm("Delete these branches?")? {
+                println!("Aborted.");
+                return Ok(());
+            }
+
+            let report = view_model.delete_selected_branches(&mut state).await;
+            print_deletion_report(&report, &mut io::stdout().lock())?;
+
+            let failed = report
+                .iter()
+                .filter(|e| {
+                    matches!(e.outcome, Some(DeletionOutcome::Failed(_)))
+                        || matches!(e.remote_outcome, Some(DeletionOutcome::Failed(_)))
+                })
+                .count();
+            if failed > 0 {
+                return Err(format!("failed to delete {} branch(es)", failed).into());
+            }
+            Ok(())
+        }
+        Command::Restore => {
+            let mut state = view_model.load_initial_state();
+            let restored = view_model.restore_deleted_branches(&mut state).await?;
+            if restored.is_empty() {
+                println!("Nothing to restore.");
+            } else {
+                println!("Restored {} branch(es):", restored.len());
+                for name in &restored {
+                    println!("  {}", name);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Drains streaming PR updates until the loader finishes and returns the final state
+/// Selection goes through the same ViewModel logic the TUI uses
+pub async fn wait_for_branches<T: BranchStore>(
+    view_model: &BranchViewModel<T>,
+    mut update_rx: UnboundedReceiver<BCBranch>,
+) -> ViewState {
+    let mut state = view_model.load_initial_state();
+    while let Some(updated_branch) = update_rx.recv().await {
+        view_model.update_branch(&mut state, updated_branch);
+    }
+    state
+}
+
+/// Writes each branch as a JSON line as soon as its PR status is known
+/// Branches that are already settled are written first, then streamed updates in arrival order
+async fn stream_ndjson<T: BranchStore>(
+    view_model: &BranchViewModel<T>,
+    mut update_rx: UnboundedReceiver<BCBranch>,
+    out: &mut impl Write,
+) -> Result<(), Box<dyn Error>> {
+    let mut state = view_model.load_initial_state();
+    for branch in state
+        .branches
+        .iter()
+        .filter(|b| b.pr_status != PrStatus::LOADING)
+    {
+        write_json_line(branch, out)?;
+    }
+
+    while let Some(updated_branch) = update_rx.recv().await {
+        write_json_line(&updated_branch, out)?;
+        view_model.update_branch(&mut state, updated_branch);
+    }
+    Ok(())
+}
+
+/// Writes a single branch as one line of JSON and flushes so pipelines see it immediately
+fn write_json_line(branch: &BCBranch, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+    serde_json::to_writer(&mut *out, branch)?;
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes one line per branch: name, PR status and PR number/title when known
+fn print_branches(branches: &[BCBranch], out: &mut impl Write) -> io::Result<()> {
+    for branch in branches {
+        write!(out, "{}", branch)?;
+        if let (Some(number), Some(title)) = (branch.pr_number, &branch.pr_title) {
+            write!(out, " | #{} {}", number, title)?;
+            if branch.prs.len() > 1 {
+                write!(out, " ({} PRs)", branch.prs.len())?;
+            }
+        }
+        if let Some(upstream) = &branch.upstream {
+            write!(out, " | {}", upstream)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Writes one line per reported branch: outcome, name, tip SHA and why it was selected
+fn print_deletion_report(report: &[DeletionReport], out: &mut impl Write) -> io::Result<()> {
+    for entry in report {
+        write!(
+            out,
+            "  {}: {} {} - {}",
+            outcome_label(&entry.outcome),
+            entry.name,
+            entry.tip_sha.as_deref().unwrap_or("unknown"),
+            entry.reason
+        )?;
+        if entry.remote {
+            write!(out, " | remote: {}", outcome_label(&entry.remote_outcome))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Describes a deletion outcome; `None` means it was only a dry run
+fn outcome_label(outcome: &Option<DeletionOutcome>) -> String {
+    match outcome {
+        None => "would delete".to_string(),
+        Some(DeletionOutcome::Deleted) => "deleted".to_string(),
+        Some(DeletionOutcome::Skipped) => "skipped (not found)".to_string(),
+        Some(DeletionOutcome::Failed(e)) => format!("FAILED ({})", e),
+    }
+}
+
+/// Asks a yes/no question on stdin, defaulting to no
+fn confirm(question: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryBranchStore;
+    use crate::UpstreamStatus;
+
+    #[test]
+    fn defaults_to_tui_without_subcommand() {
+        let cli = Cli::try_parse_from(["branch-cleaner-gh"]).unwrap();
+        assert_eq!(cli.command(), Command::Tui);
+    }
+
+    #[test]
+    fn parses_clean_flags() {
+        let cli = Cli::try_parse_from(["branch-cleaner-gh", "clean", "--merged", "--yes"]).unwrap();
+        assert_eq!(
+            cli.command(),
+            Command::Clean {
+                merged: true,
+                gone: false,
+                yes: true
+            }
+        );
+
+        let cli = Cli::try_parse_from(["branch-cleaner-gh", "clean", "--gone"]).unwrap();
+        assert_eq!(
+            cli.command(),
+            Command::Clean {
+                merged: false,
+                gone: true,
+                yes: false
+            }
+        );
+    }
+
+    #[test]
+    fn remote_branches_flag_selects_remote_scope() {
+        let cli = Cli::try_parse_from(["branch-cleaner-gh", "list", "--remote-branches"]).unwrap();
+        assert_eq!(cli.scope(), BranchScope::Remote);
+        assert_eq!(
+            Cli::try_parse_from(["branch-cleaner-gh"]).unwrap().scope(),
+            BranchScope::Local
+        );
+
+        // Remote branches are already on the remote; there is nothing extra to delete
+        assert!(
+            Cli::try_parse_from(["branch-cleaner-gh", "--remote-branches", "--delete-remote"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn concurrency_flag_overrides_config() {
+        let cli = Cli::try_parse_from(["branch-cleaner-gh", "--concurrency", "16"]).unwrap();
+        let mut config = Config::parse("github = { concurrency = 4 }").unwrap();
+
+        cli.apply_to(&mut config);
+
+        assert_eq!(config.github.concurrency, Some(16));
+        assert!(Cli::try_parse_from(["branch-cleaner-gh", "--concurrency", "0"]).is_err());
+    }
+
+    #[test]
+    fn remote_flag_overrides_config() {
+        let cli = Cli::try_parse_from(["branch-cleaner-gh", "list", "--remote", "fork"]).unwrap();
+        let mut config = Config::parse("defaults = { remote = \"origin\" }").unwrap();
+
+        cli.apply_to(&mut config);
+
+        assert_eq!(config.defaults.remote.as_deref(), Some("fork"));
+    }
+
+    #[test]
+    fn policy_flag_replaces_the_individual_rules() {
+        let cli =
+            Cli::try_parse_from(["branch-cleaner-gh", "--policy", "gone and stale:30"]).unwrap();
+        let mut config = Config::parse("select = { policy = \"merged\" }").unwrap();
+
+        cli.apply_to(&mut config);
+
+        assert_eq!(config.select.policy.as_deref(), Some("gone and stale:30"));
+        assert!(Cli::try_parse_from([
+            "branch-cleaner-gh",
+            "--policy",
+            "merged",
+            "--stale-days",
+            "9"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn dry_run_is_accepted_before_or_after_subcommand() {
+        let before = Cli::try_parse_from(["branch-cleaner-gh", "--dry-run", "clean"]).unwrap();
+        let after = Cli::try_parse_from(["branch-cleaner-gh", "clean", "--dry-run"]).unwrap();
+        assert!(before.dry_run);
+        assert!(after.dry_run);
+    }
+
+    #[test]
+    fn parses_list_format() {
+        let cli = Cli::try_parse_from(["branch-cleaner-gh", "list", "--format", "ndjson"]).unwrap();
+        assert_eq!(
+            cli.command(),
+            Command::List {
+                format: OutputFormat::Ndjson
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_for_branches_applies_streamed_updates() {
+        // Arrange: Store with LOADING branches and a channel carrying one update
+        let loading_branches = vec![
+            BCBranch::new("feature-open", PrStatus::LOADING),
+            BCBranch::new("feature-merged", PrStatus::LOADING),
+        ];
+        let view_model = BranchViewModel::new(InMemoryBranchStore::new(loading_branches));
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tx.send(BCBranch::with_pr(
+            "feature-merged",
+            PrStatus::MERGED,
+            7,
+            "Done",
+        ))
+        .unwrap();
+        drop(tx);
+
+        // Act
+        let state = wait_for_branches(&view_model, rx).await;
+
+        // Assert: Same auto-selection as the TUI
+        assert_eq!(state.branches[1].pr_status, PrStatus::MERGED);
+        assert_eq!(state.selected_branches, vec!["feature-merged".to_owned()]);
+    }
+
+    #[test]
+    fn deletion_report_shows_outcome_per_branch() {
+        let report = vec![
+            DeletionReport {
+                name: "feature".to_owned(),
+                tip_sha: Some("abc1234".to_owned()),
+                reason: "PR #1 merged".to_owned(),
+                outcome: Some(DeletionOutcome::Deleted),
+                remote: true,
+                remote_outcome: Some(DeletionOutcome::Deleted),
+            },
+            DeletionReport {
+                name: "gone".to_owned(),
+                tip_sha: None,
+                reason: "selected manually".to_owned(),
+                outcome: Some(DeletionOutcome::Skipped),
+                remote: false,
+                remote_outcome: None,
+            },
+        ];
+        let mut out = Vec::new();
+
+        print_deletion_report(&report, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "  deleted: feature abc1234 - PR #1 merged | remote: deleted\n  skipped (not found): gone unknown - selected manually\n"
+        );
+    }
+
+    #[test]
+    fn print_branches_includes_pr_info() {
+        let branches = vec![
+            BCBranch::new("experiment", PrStatus::NONE),
+            BCBranch::with_pr("feature", PrStatus::MERGED, 12, "Add feature").with_upstream(Some(
+                UpstreamStatus::Gone {
+                    name: "origin/feature".to_owned(),
+                },
+            )),
+        ];
+        let mut out = Vec::new();
+
+        print_branches(&branches, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "experiment | No PR\nfeature | merged | #12 Add feature | origin/feature: gone\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_ndjson_writes_settled_then_streamed_branches() {
+        // Arrange: One settled branch and one still loading
+        let branches = vec![
+            BCBranch::new("experiment", PrStatus::NONE),
+            BCBranch::new("feature", PrStatus::LOADING),
+        ];
+        let view_model = BranchViewModel::new(InMemoryBranchStore::new(branches));
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tx.send(BCBranch::with_pr("feature", PrStatus::OPEN, 3, "WIP"))
+            .unwrap();
+        drop(tx);
+        let mut out = Vec::new();
+
+        // Act
+        stream_ndjson(&view_model, rx, &mut out).await.unwrap();
+
+        // Assert: One JSON object per line, loading branch only emitted once enriched
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"name":"experiment","pr_status":"none","pr_number":null,"pr_title":null,"prs":[],"tip_sha":null,"last_commit":null,"upstream":null}"#,
+                "\n",
+                r#"{"name":"feature","pr_status":"open","pr_number":3,"pr_title":"WIP","prs":[{"number":3,"title":"WIP","status":"open","closed_at":null,"head_sha":null}],"tip_sha":null,"last_commit":null,"upstream":null}"#,
+                "\n",
+            )
+        );
+    }
+}
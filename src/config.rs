@@ -0,0 +1,387 @@
+#[cfg(feature = "github-api")]
+use globset::{Glob, GlobSet, GlobSetBuilder};
+#[cfg(feature = "github-api")]
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::{BranchCleanerError, Result};
+
+/// Branch names that are always protected, whatever the config says
+#[cfg(feature = "github-api")]
+const BUILTIN_PROTECTED: &[&str] = &["main", "master", "develop", "development"];
+
+/// File name of the per-repository config, looked up in the repository root
+const REPO_CONFIG_FILE: &str = ".branch-cleaner.toml";
+
+/// Settings read from `.branch-cleaner.toml` (per repo) and
+/// `$XDG_CONFIG_HOME/branch-cleaner/config.toml` (per user)
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub protect: ProtectConfig,
+    pub defaults: DefaultsConfig,
+    pub select: SelectConfig,
+    pub github: GitHubConfig,
+}
+
+/// Branches that are never listed or deleted, on top of the built-in names
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProtectConfig {
+    /// Exact branch names, e.g. `staging`
+    pub names: Vec<String>,
+    /// Glob patterns, e.g. `release/*`
+    pub globs: Vec<String>,
+    /// Regular expressions matched against the whole branch name
+    pub regexes: Vec<String>,
+}
+
+/// Default values for command-line options
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefaultsConfig {
+    /// Run in dry-run mode unless told otherwise
+    pub dry_run: Option<bool>,
+    /// Pre-select branches whose upstream was deleted, on top of merged ones
+    pub select_gone: Option<bool>,
+    /// Delete branches on the remote too
+    pub delete_remote: Option<bool>,
+    /// Remote whose branches are managed (default `origin`)
+    pub remote: Option<String>,
+}
+
+/// Rules pre-selecting branches for deletion
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelectConfig {
+    /// Policy expression, e.g. `merged or (gone and stale:90)`; replaces every other rule option
+    pub policy: Option<String>,
+    /// Select branches without commits for this many days (and no open PR)
+    pub stale_days: Option<u64>,
+    /// Select branches whose PR was closed without merging at least this many days ago
+    pub closed_days: Option<u64>,
+}
+
+/// How the GitHub API is used
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitHubConfig {
+    /// Maximum number of PR lookups in flight at once
+    pub concurrency: Option<usize>,
+    /// Seconds a cached open, closed (or PR-less) result is trusted before it is revalidated
+    pub cache_ttl: Option<u64>,
+    /// Per-host settings, keyed by host name (e.g. `github.example.com`)
+    pub hosts: HashMap<String, HostConfig>,
+}
+
+/// Settings for one GitHub host (github.com or a GitHub Enterprise Server)
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HostConfig {
+    /// REST API base URL, when it is not the usual `https://<host>/api/v3`
+    pub api_url: Option<String>,
+    /// Token used for this host instead of the environment
+    pub token: Option<String>,
+}
+
+impl Config {
+    /// Loads the user config, then layers the config of the repository containing `path` on top
+    /// Missing files are not an error; invalid ones are
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let mut config = Config::default();
+
+        if let Some(user_path) = user_config_path() {
+            if let Some(user) = Self::read_file(&user_path)? {
+                config.merge(user);
+            }
+        }
+
+        if let Some(repo_path) = repo_config_path(path.as_ref()) {
+            if let Some(repo) = Self::read_file(&repo_path)? {
+                config.merge(repo);
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Parses a config from TOML text
+    pub fn parse(contents: &str) -> Result<Self> {
+        toml::from_str(contents).map_err(|e| BranchCleanerError::ConfigError(e.to_string()))
+    }
+
+    fn read_file(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path)?;
+        Self::parse(&contents)
+            .map(Some)
+            .map_err(|e| BranchCleanerError::ConfigError(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Layers `other` on top of `self`: lists are combined, scalar options are overridden
+    pub fn merge(&mut self, other: Config) {
+        self.protect.names.extend(other.protect.names);
+        self.protect.globs.extend(other.protect.globs);
+        self.protect.regexes.extend(other.protect.regexes);
+
+        if other.defaults.dry_run.is_some() {
+            self.defaults.dry_run = other.defaults.dry_run;
+        }
+        if other.defaults.select_gone.is_some() {
+            self.defaults.select_gone = other.defaults.select_gone;
+        }
+        if other.defaults.delete_remote.is_some() {
+            self.defaults.delete_remote = other.defaults.delete_remote;
+        }
+        if other.defaults.remote.is_some() {
+            self.defaults.remote = other.defaults.remote;
+        }
+        if other.select.policy.is_some() {
+            self.select.policy = other.select.policy;
+        }
+        if other.select.stale_days.is_some() {
+            self.select.stale_days = other.select.stale_days;
+        }
+        if other.select.closed_days.is_some() {
+            self.select.closed_days = other.select.closed_days;
+        }
+        if other.github.concurrency.is_some() {
+            self.github.concurrency = other.github.concurrency;
+        }
+        if other.github.cache_ttl.is_some() {
+            self.github.cache_ttl = other.github.cache_ttl;
+        }
+        for (host, other_host) in other.github.hosts {
+            let host = self.github.hosts.entry(host).or_default();
+            if other_host.api_url.is_some() {
+                host.api_url = other_host.api_url;
+            }
+            if other_host.token.is_some() {
+                host.token = other_host.token;
+            }
+        }
+    }
+
+    /// Settings for a GitHub host (empty when the host is not configured)
+    #[cfg(feature = "github-api")]
+    pub fn host(&self, host: &str) -> HostConfig {
+        self.github.hosts.get(host).cloned().unwrap_or_default()
+    }
+
+    /// Compiles the protection rules (built-in names plus configured names and patterns)
+    #[cfg(feature = "github-api")]
+    pub fn protection_rules(&self) -> Result<ProtectionRules> {
+        ProtectionRules::new(&self.protect)
+    }
+}
+
+/// `$XDG_CONFIG_HOME/branch-cleaner/config.toml` (or the platform equivalent)
+fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("branch-cleaner").join("config.toml"))
+}
+
+/// `.branch-cleaner.toml` in the root of the repository containing `path`
+fn repo_config_path(path: &Path) -> Option<PathBuf> {
+    let repo = git2::Repository::discover(path).ok()?;
+    repo.workdir().map(|dir| dir.join(REPO_CONFIG_FILE))
+}
+
+/// Compiled matcher deciding which branches must never be deleted
+#[cfg(feature = "github-api")]
+#[derive(Debug, Clone)]
+pub struct ProtectionRules {
+    names: Vec<String>,
+    globs: GlobSet,
+    regexes: Vec<Regex>,
+}
+
+#[cfg(feature = "github-api")]
+impl Default for ProtectionRules {
+    fn default() -> Self {
+        Self {
+            names: BUILTIN_PROTECTED.iter().map(|s| s.to_string()).collect(),
+            globs: GlobSet::empty(),
+            regexes: Vec::new(),
+        }
+    }
+}
+
+#[cfg(feature = "github-api")]
+impl ProtectionRules {
+    /// Builds rules from config, always including the built-in names
+    pub fn new(protect: &ProtectConfig) -> Result<Self> {
+        let mut names: Vec<String> = BUILTIN_PROTECTED.iter().map(|s| s.to_string()).collect();
+        names.extend(protect.names.iter().cloned());
+
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &protect.globs {
+            let glob = Glob::new(pattern).map_err(|e| {
+                BranchCleanerError::ConfigError(format!("invalid glob '{}': {}", pattern, e))
+            })?;
+            builder.add(glob);
+        }
+        let globs = builder
+            .build()
+            .map_err(|e| BranchCleanerError::ConfigError(e.to_string()))?;
+
+        let regexes = protect
+            .regexes
+            .iter()
+            .map(|pattern| {
+                // Anchor so patterns match the whole name, like globs do
+                Regex::new(&format!("^(?:{})$", pattern)).map_err(|e| {
+                    BranchCleanerError::ConfigError(format!("invalid regex '{}': {}", pattern, e))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            names,
+            globs,
+            regexes,
+        })
+    }
+
+    /// Returns true if the branch must never be offered for deletion
+    pub fn is_protected(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name)
+            || self.globs.is_match(name)
+            || self.regexes.iter().any(|re| re.is_match(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "github-api")]
+    #[test]
+    fn builtin_names_are_always_protected() {
+        let rules = Config::default().protection_rules().unwrap();
+
+        assert!(rules.is_protected("main"));
+        assert!(rules.is_protected("develop"));
+        assert!(!rules.is_protected("feature/x"));
+    }
+
+    #[cfg(feature = "github-api")]
+    #[test]
+    fn parses_names_globs_and_regexes() {
+        let config = Config::parse(
+            r#"
+            [protect]
+            names = ["staging"]
+            globs = ["release/*", "hotfix/*"]
+            regexes = ["prod-\\d+"]
+
+            [defaults]
+            dry_run = true
+            select_gone = true
+
+            [github]
+            concurrency = 4
+            cache_ttl = 60
+            "#,
+        )
+        .unwrap();
+        let rules = config.protection_rules().unwrap();
+
+        assert!(rules.is_protected("staging"));
+        assert!(rules.is_protected("release/1.2"));
+        assert!(rules.is_protected("hotfix/urgent"));
+        assert!(rules.is_protected("prod-42"));
+        assert!(!rules.is_protected("my-prod-42-fix"));
+        assert!(!rules.is_protected("feature/release"));
+        assert_eq!(config.defaults.dry_run, Some(true));
+        assert_eq!(config.defaults.select_gone, Some(true));
+        assert_eq!(config.github.concurrency, Some(4));
+        assert_eq!(config.github.cache_ttl, Some(60));
+    }
+
+    #[test]
+    fn repo_config_is_layered_on_user_config() {
+        let mut config = Config::parse(
+            r#"
+            protect = { names = ["staging"] }
+            defaults = { dry_run = true, remote = "fork" }
+            "#,
+        )
+        .unwrap();
+        let repo = Config::parse(
+            r#"
+            protect = { globs = ["release/*"] }
+            defaults = { dry_run = false }
+            select = { stale_days = 90 }
+            "#,
+        )
+        .unwrap();
+
+        config.merge(repo);
+
+        assert_eq!(config.protect.names, vec!["staging".to_owned()]);
+        assert_eq!(config.protect.globs, vec!["release/*".to_owned()]);
+        assert_eq!(config.defaults.dry_run, Some(false));
+        assert_eq!(config.defaults.remote.as_deref(), Some("fork"));
+        assert_eq!(config.select.stale_days, Some(90));
+    }
+
+    #[cfg(feature = "github-api")]
+    #[test]
+    fn host_settings_are_merged_per_field() {
+        let mut config = Config::parse(
+            r#"
+            [github.hosts."github.example.com"]
+            token = "user-token"
+            "#,
+        )
+        .unwrap();
+        let repo = Config::parse(
+            r#"
+            [github.hosts."github.example.com"]
+            api_url = "https://github.example.com/custom/api"
+            "#,
+        )
+        .unwrap();
+
+        config.merge(repo);
+
+        assert_eq!(
+            config.host("github.example.com"),
+            HostConfig {
+                api_url: Some("https://github.example.com/custom/api".to_owned()),
+                token: Some("user-token".to_owned()),
+            }
+        );
+        assert_eq!(config.host("github.com"), HostConfig::default());
+    }
+
+    #[cfg(feature = "github-api")]
+    #[test]
+    fn invalid_patterns_are_errors() {
+        let config = Config::parse(r#"protect = { regexes = ["(unclosed"] }"#).unwrap();
+        assert!(config.protection_rules().is_err());
+        assert!(Config::parse("unknown_key = 1").is_err());
+    }
+
+    #[test]
+    fn loads_repo_config_from_repository_root() {
+        let dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(dir.path()).unwrap();
+        fs::write(
+            dir.path().join(REPO_CONFIG_FILE),
+            "protect = { names = [\"trunk\"] }",
+        )
+        .unwrap();
+        let nested = dir.path().join("src");
+        fs::create_dir(&nested).unwrap();
+
+        let config = Config::load(&nested).unwrap();
+
+        assert!(config.protect.names.contains(&"trunk".to_owned()));
+    }
+}
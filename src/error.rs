@@ -6,17 +6,57 @@ pub enum BranchCleanerError {
     #[error("Git error: {0}")]
     GitError(#[from] git2::Error),
 
+    #[cfg(feature = "github-api")]
     #[error("GitHub API error: {0}")]
     GitHubError(#[from] octocrab::Error),
 
+    #[cfg(feature = "github-api")]
     #[error("Remote URL parsing error: {0}")]
     RemoteParseError(String),
 
-    #[error("GitHub token not found in environment")]
+    #[cfg(feature = "github-api")]
+    #[error("No GitHub token found: set GITHUB_TOKEN or GH_TOKEN (GH_ENTERPRISE_TOKEN for Enterprise hosts), run `gh auth login`, store one in a git credential helper, or set `token` under [github.hosts] in the config")]
     TokenNotFound,
 
-    #[error("No origin remote found in repository")]
-    NoOriginRemote,
+    #[cfg(feature = "github-api")]
+    #[error("No remote named '{0}' found in repository")]
+    NoRemote(String),
+
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Deletion journal error: {0}")]
+    JournalError(#[from] serde_json::Error),
+
+    #[cfg(feature = "github-api")]
+    #[error("Could not record branch in deletion journal: {0}")]
+    JournalWriteFailed(String),
+
+    #[error("Config error: {0}")]
+    ConfigError(String),
+
+    #[error("Refusing to delete remote branch: {0}")]
+    RemoteDeleteRefused(String),
+
+    #[cfg(feature = "github-api")]
+    #[error("Push to remote failed: {0}")]
+    PushFailed(String),
+
+    #[cfg(feature = "github-api")]
+    #[error("Could not recreate {0} (undo again to retry)")]
+    RestoreFailed(String),
+
+    #[cfg(feature = "github-api")]
+    #[error("GitHub rate limit exceeded (resets in {0}s)")]
+    RateLimited(u64),
+
+    #[cfg(feature = "github-api")]
+    #[error("GitHub API returned {0}: {1}")]
+    GitHubStatus(u16, String),
+
+    #[cfg(feature = "github-api")]
+    #[error("GitHub GraphQL query failed: {0}")]
+    GraphQl(String),
 }
 
 pub type Result<T> = std::result::Result<T, BranchCleanerError>;
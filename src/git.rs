@@ -1,20 +1,52 @@
-use git2::{BranchType, Repository};
+use git2::{BranchType, Cred, CredentialType, Oid, PushOptions, RemoteCallbacks, Repository};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 
+use crate::config::ProtectionRules;
 use crate::error::{BranchCleanerError, Result};
+use crate::journal::{DeletionJournal, JournalEntry};
+use crate::pr_cache::PrCache;
+use crate::store::{DeletionOutcome, DeletionResult};
+use crate::{CommitInfo, UpstreamStatus};
+
+/// Remote whose branches are managed unless `--remote` says otherwise
+pub const DEFAULT_REMOTE: &str = "origin";
+
+/// Conventional name of the repository a fork was made from, where PRs are opened
+pub const UPSTREAM_REMOTE: &str = "upstream";
+
+/// How a branch's work reached the base branch, judged from git history alone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocalMerge {
+    /// The branch tip is reachable from the base (regular merge or fast-forward)
+    Ancestor,
+    /// Every commit on the branch has an equivalent commit (same patch-id) on the base
+    RebaseMerged,
+    /// The branch's combined changes are already present in the base tree
+    SquashMerged,
+}
 
 /// GitRepository wraps git2::Repository with convenience methods
 /// Stores the repo path to enable cloning by reopening
 pub struct GitRepository {
     repo: Repository,
     path: PathBuf,
+    protection: ProtectionRules,
+    default_branch: Option<String>,
+    remote: String,
 }
 
 impl Clone for GitRepository {
     fn clone(&self) -> Self {
         // Clone by reopening the repository at the same path
         // This is necessary because git2::Repository doesn't implement Clone
-        Self::open(&self.path).expect("Failed to reopen repository")
+        Self::open(&self.path)
+            .expect("Failed to reopen repository")
+            .with_protection(self.protection.clone())
+            .with_default_branch(self.default_branch.clone())
+            .with_remote(&self.remote)
     }
 }
 
@@ -24,15 +56,222 @@ impl Clone for GitRepository {
 unsafe impl Sync for GitRepository {}
 unsafe impl Send for GitRepository {}
 
-/// Branch names that should never be deleted
-const PROTECTED_BRANCHES: &[&str] = &["main", "master", "develop", "development"];
-
 impl GitRepository {
     /// Opens repository at the given path (or discovers from current dir)
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
         let repo = Repository::discover(path.as_ref())?;
         let path = repo.path().parent().unwrap_or(repo.path()).to_path_buf();
-        Ok(Self { repo, path })
+        Ok(Self {
+            repo,
+            path,
+            protection: ProtectionRules::default(),
+            default_branch: None,
+            remote: DEFAULT_REMOTE.to_owned(),
+        })
+    }
+
+    /// Replaces the rules deciding which branches are never listed for deletion
+    pub fn with_protection(mut self, protection: ProtectionRules) -> Self {
+        self.protection = protection;
+        self
+    }
+
+    /// Sets the repository's default branch, which is always protected
+    pub fn with_default_branch(mut self, default_branch: Option<String>) -> Self {
+        self.default_branch = default_branch;
+        self
+    }
+
+    /// Sets the remote whose branches are listed, deleted and pushed to (`origin` by default)
+    pub fn with_remote(mut self, remote: &str) -> Self {
+        self.remote = remote.to_owned();
+        self
+    }
+
+    /// Name of the remote whose branches are managed
+    pub fn remote(&self) -> &str {
+        &self.remote
+    }
+
+    /// The repository's default branch (base for merge checks), if known
+    pub fn default_branch(&self) -> Option<&str> {
+        self.default_branch.as_deref()
+    }
+
+    /// Reads the default branch from `refs/remotes/<remote>/HEAD` (set by `git clone` or
+    /// `git remote set-head <remote> --auto`), trying `upstream` before the managed remote,
+    /// and falling back to a local `main` or `master`
+    pub fn local_default_branch(&self) -> Option<String> {
+        let from_remote_head = self.base_remotes().into_iter().find_map(|remote| {
+            let prefix = format!("refs/remotes/{}/", remote);
+            let reference = self.repo.find_reference(&format!("{}HEAD", prefix)).ok()?;
+            reference
+                .symbolic_target()?
+                .strip_prefix(&prefix)
+                .map(|name| name.to_owned())
+        });
+
+        from_remote_head.or_else(|| {
+            ["main", "master"]
+                .iter()
+                .find(|name| self.repo.find_branch(name, BranchType::Local).is_ok())
+                .map(|name| name.to_string())
+        })
+    }
+
+    /// Checks whether a local branch's work is already in `base` without asking GitHub
+    /// `base` is resolved as `upstream/<base>` or `<remote>/<base>` when available,
+    /// otherwise the local branch
+    pub fn local_merge_status(&self, name: &str, base: &str) -> Result<Option<LocalMerge>> {
+        match self.branch_tip(name)? {
+            Some(sha) => self.merge_status_of(Oid::from_str(&sha)?, base),
+            None => Ok(None),
+        }
+    }
+
+    /// Same as `local_merge_status`, for `<remote>/<name>` instead of the local branch
+    pub fn remote_merge_status(&self, name: &str, base: &str) -> Result<Option<LocalMerge>> {
+        match self.repo.refname_to_id(&self.remote_ref(name)) {
+            Ok(tip) => self.merge_status_of(tip, base),
+            Err(_) => Ok(None),
+        }
+    }
+
+    fn merge_status_of(&self, branch_tip: Oid, base: &str) -> Result<Option<LocalMerge>> {
+        let base_tip = match self.resolve_base(base) {
+            Some(oid) => oid,
+            None => return Ok(None),
+        };
+
+        if branch_tip == base_tip || self.repo.graph_descendant_of(base_tip, branch_tip)? {
+            return Ok(Some(LocalMerge::Ancestor));
+        }
+
+        let merge_base = match self.repo.merge_base(base_tip, branch_tip) {
+            Ok(oid) => oid,
+            Err(_) => return Ok(None), // Unrelated histories
+        };
+
+        if self.is_rebase_merged(branch_tip, base_tip, merge_base)? {
+            return Ok(Some(LocalMerge::RebaseMerged));
+        }
+        if self.is_squash_merged(branch_tip, base_tip, merge_base)? {
+            return Ok(Some(LocalMerge::SquashMerged));
+        }
+        Ok(None)
+    }
+
+    /// Reads the time, author and subject of a commit (`None` when it is not in the repository)
+    pub fn commit_info(&self, sha: &str) -> Option<CommitInfo> {
+        let commit = self.repo.find_commit(Oid::from_str(sha).ok()?).ok()?;
+        let author = commit.author();
+        Some(CommitInfo {
+            time: commit.time().seconds(),
+            author_name: author.name().unwrap_or_default().to_owned(),
+            author_email: author.email().unwrap_or_default().to_owned(),
+            subject: commit.summary().unwrap_or_default().to_owned(),
+        })
+    }
+
+    /// Counts the commits reachable from `tip` but not from `merged_head`, the head of a merged PR
+    /// When `merged_head` is not in the local repository, commits not yet in `base` are counted
+    /// instead; `None` when neither can be resolved
+    pub fn commits_after(
+        &self,
+        tip: &str,
+        merged_head: &str,
+        base: Option<&str>,
+    ) -> Result<Option<usize>> {
+        let known_head = Oid::from_str(merged_head)
+            .ok()
+            .filter(|oid| self.repo.find_commit(*oid).is_ok());
+        let hide = match known_head.or_else(|| base.and_then(|base| self.resolve_base(base))) {
+            Some(oid) => oid,
+            None => return Ok(None),
+        };
+
+        let mut walk = self.repo.revwalk()?;
+        walk.push(Oid::from_str(tip)?)?;
+        walk.hide(hide)?;
+        Ok(Some(walk.count()))
+    }
+
+    /// Resolves the base branch tip, preferring the remote-tracking refs
+    /// In a fork, `upstream/<base>` is where PRs land, so it wins over the fork's copy
+    fn resolve_base(&self, base: &str) -> Option<Oid> {
+        self.base_remotes()
+            .into_iter()
+            .find_map(|remote| {
+                self.repo
+                    .refname_to_id(&format!("refs/remotes/{}/{}", remote, base))
+                    .ok()
+            })
+            .or_else(|| {
+                self.repo
+                    .refname_to_id(&format!("refs/heads/{}", base))
+                    .ok()
+            })
+    }
+
+    /// Remotes that may hold the base branch: `upstream` (when it exists), then the managed remote
+    fn base_remotes(&self) -> Vec<&str> {
+        let mut remotes = Vec::new();
+        if self.upstream_remote().is_some() {
+            remotes.push(UPSTREAM_REMOTE);
+        }
+        remotes.push(self.remote.as_str());
+        remotes
+    }
+
+    /// True when every non-merge commit in `merge_base..branch_tip` has a
+    /// commit with the same patch-id in `merge_base..base_tip`
+    fn is_rebase_merged(&self, branch_tip: Oid, base_tip: Oid, merge_base: Oid) -> Result<bool> {
+        let branch_ids = self.patch_ids(branch_tip, merge_base)?;
+        if branch_ids.is_empty() {
+            return Ok(false);
+        }
+        let base_ids: HashSet<Oid> = self.patch_ids(base_tip, merge_base)?.into_iter().collect();
+        Ok(branch_ids.iter().all(|id| base_ids.contains(id)))
+    }
+
+    /// True when merging the branch into the base would not change the base tree
+    fn is_squash_merged(&self, branch_tip: Oid, base_tip: Oid, merge_base: Oid) -> Result<bool> {
+        let ancestor = self.repo.find_commit(merge_base)?.tree()?;
+        let ours = self.repo.find_commit(base_tip)?.tree()?;
+        let theirs = self.repo.find_commit(branch_tip)?.tree()?;
+
+        let index = self.repo.merge_trees(&ancestor, &ours, &theirs, None)?;
+        if index.has_conflicts() {
+            return Ok(false);
+        }
+        let diff = self
+            .repo
+            .diff_tree_to_index(Some(&ours), Some(&index), None)?;
+        Ok(diff.deltas().len() == 0)
+    }
+
+    /// Patch-ids of the non-merge commits reachable from `tip` but not from `hide`
+    fn patch_ids(&self, tip: Oid, hide: Oid) -> Result<Vec<Oid>> {
+        let mut walk = self.repo.revwalk()?;
+        walk.push(tip)?;
+        walk.hide(hide)?;
+
+        let mut ids = Vec::new();
+        for oid in walk {
+            let commit = self.repo.find_commit(oid?)?;
+            if commit.parent_count() > 1 {
+                continue;
+            }
+            let parent_tree = match commit.parent(0) {
+                Ok(parent) => Some(parent.tree()?),
+                Err(_) => None,
+            };
+            let diff =
+                self.repo
+                    .diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+            ids.push(diff.patchid(None)?);
+        }
+        Ok(ids)
     }
 
     /// Gets the name of the currently checked out branch (HEAD)
@@ -49,7 +288,7 @@ impl GitRepository {
         }
     }
 
-    /// Lists all local branch names, excluding protected branches and HEAD
+    /// Lists all local branch names, excluding protected branches, the default branch and HEAD
     pub fn list_local_branches(&self) -> Result<Vec<String>> {
         let current = self.current_branch()?;
 
@@ -57,13 +296,11 @@ impl GitRepository {
             .repo
             .branches(Some(BranchType::Local))?
             .filter_map(|b| {
-                b.ok().and_then(|(branch, _)| {
-                    branch.name().ok()?.map(|s| s.to_owned())
-                })
+                b.ok()
+                    .and_then(|(branch, _)| branch.name().ok()?.map(|s| s.to_owned()))
             })
             .filter(|name| {
-                // Exclude protected branches
-                !PROTECTED_BRANCHES.contains(&name.as_str())
+                !self.is_protected(name)
                     // Exclude current HEAD branch
                     && current.as_ref() != Some(name)
             })
@@ -71,22 +308,324 @@ impl GitRepository {
         Ok(branches)
     }
 
-    /// Deletes local branches by name
-    pub fn delete_branches(&self, names: &[String]) -> Result<()> {
+    /// Lists branches on the remote from `refs/remotes/<remote>/*`, excluding protected branches
+    /// and the default branch (no request is made: this is as fresh as the last fetch)
+    pub fn list_remote_branches(&self) -> Result<Vec<String>> {
+        let branches = self
+            .repo
+            .branches(Some(BranchType::Remote))?
+            .filter_map(|b| {
+                b.ok()
+                    .and_then(|(branch, _)| branch.name().ok()?.map(|s| s.to_owned()))
+            })
+            .filter_map(|name| {
+                name.strip_prefix(&format!("{}/", self.remote))
+                    .map(|s| s.to_owned())
+            })
+            .filter(|name| name != "HEAD" && !self.is_protected(name))
+            .collect();
+        Ok(branches)
+    }
+
+    /// Gets the SHA of the commit `<remote>/<name>` points to
+    pub fn remote_branch_tip(&self, name: &str) -> Option<String> {
+        self.repo
+            .refname_to_id(&self.remote_ref(name))
+            .ok()
+            .map(|oid| oid.to_string())
+    }
+
+    /// Removes the remote-tracking ref of a branch deleted on the remote, like `git fetch --prune`
+    pub fn prune_remote_ref(&self, name: &str) {
+        if let Ok(mut reference) = self.repo.find_reference(&self.remote_ref(name)) {
+            let _ = reference.delete();
+        }
+    }
+
+    /// Protected branches and the default branch, whatever it is called, are never deleted
+    pub fn is_protected(&self, name: &str) -> bool {
+        self.protection.is_protected(name) || self.default_branch.as_deref() == Some(name)
+    }
+
+    /// Gets the SHA of the commit a local branch points to
+    pub fn branch_tip(&self, name: &str) -> Result<Option<String>> {
+        let branch = match self.repo.find_branch(name, BranchType::Local) {
+            Ok(branch) => branch,
+            Err(_) => return Ok(None),
+        };
+        Ok(branch.get().target().map(|oid| oid.to_string()))
+    }
+
+    /// Reports whether a branch's configured upstream still exists and how far apart they are
+    /// Returns `None` when no upstream is configured
+    pub fn upstream_status(&self, name: &str) -> Result<Option<UpstreamStatus>> {
+        let config = self.repo.config()?;
+        let remote = config.get_string(&format!("branch.{}.remote", name)).ok();
+        let merge = config.get_string(&format!("branch.{}.merge", name)).ok();
+        let (remote, merge) = match (remote, merge) {
+            (Some(remote), Some(merge)) => (remote, merge),
+            _ => return Ok(None),
+        };
+
+        let short = merge.strip_prefix("refs/heads/").unwrap_or(&merge);
+        let (upstream_name, upstream_ref) = if remote == "." {
+            // Tracking another local branch
+            (short.to_owned(), merge.clone())
+        } else {
+            (
+                format!("{}/{}", remote, short),
+                format!("refs/remotes/{}/{}", remote, short),
+            )
+        };
+
+        let upstream_tip = match self.repo.refname_to_id(&upstream_ref) {
+            Ok(oid) => oid,
+            Err(_) => {
+                return Ok(Some(UpstreamStatus::Gone {
+                    name: upstream_name,
+                }))
+            }
+        };
+        let local_tip = self.repo.refname_to_id(&format!("refs/heads/{}", name))?;
+        let (ahead, behind) = self.repo.graph_ahead_behind(local_tip, upstream_tip)?;
+
+        Ok(Some(UpstreamStatus::Tracking {
+            name: upstream_name,
+            ahead,
+            behind,
+        }))
+    }
+
+    /// Deletes local branches by name, returning one result per name
+    /// Each branch is recorded in the deletion journal first so it can be restored;
+    /// branches that cannot be journaled are not deleted
+    pub fn delete_branches(&self, names: &[String]) -> Vec<DeletionResult> {
+        let mut outcomes: Vec<Option<DeletionOutcome>> = Vec::new();
+        let mut to_delete = Vec::new();
+        let mut entries = Vec::new();
+        for name in names {
+            let branch = match self.repo.find_branch(name, BranchType::Local) {
+                Ok(branch) => branch,
+                Err(_) => {
+                    outcomes.push(Some(DeletionOutcome::Skipped));
+                    continue;
+                }
+            };
+            let entry = match branch.get().target() {
+                Some(tip) => self.journal_entry(name, tip),
+                None => Err(BranchCleanerError::JournalWriteFailed(format!(
+                    "{} does not point to a commit",
+                    name
+                ))),
+            };
+            match entry {
+                Ok(entry) => {
+                    entries.push(entry);
+                    to_delete.push((outcomes.len(), branch));
+                    outcomes.push(None);
+                }
+                Err(e) => outcomes.push(Some(DeletionOutcome::Failed(e))),
+            }
+        }
+
+        if let Err(e) = self.journal().record(entries) {
+            // Without a journal entry there is no undo, so delete nothing
+            let message = e.to_string();
+            for (index, _) in to_delete.drain(..) {
+                outcomes[index] = Some(DeletionOutcome::Failed(
+                    BranchCleanerError::JournalWriteFailed(message.clone()),
+                ));
+            }
+        }
+
+        for (index, mut branch) in to_delete {
+            outcomes[index] = Some(match branch.delete() {
+                Ok(()) => DeletionOutcome::Deleted,
+                Err(e) => DeletionOutcome::Failed(e.into()),
+            });
+        }
+
+        names
+            .iter()
+            .zip(outcomes)
+            .map(|(name, outcome)| {
+                DeletionResult::new(name, outcome.unwrap_or(DeletionOutcome::Skipped))
+            })
+            .collect()
+    }
+
+    /// Deletes `<remote>/<name>` on the remote for each branch, returning one result per name
+    /// Protected branches are refused; branches without a remote-tracking ref are skipped.
+    /// Authenticates with the SSH agent or the repository's git credential helpers
+    pub fn delete_remote_branches(&self, names: &[String]) -> Vec<DeletionResult> {
+        let mut outcomes: Vec<Option<DeletionOutcome>> = Vec::new();
+        let mut to_push = Vec::new();
         for name in names {
-            if let Ok(mut branch) = self.repo.find_branch(name, BranchType::Local) {
-                branch.delete()?;
+            if self.is_protected(name) {
+                outcomes.push(Some(DeletionOutcome::Failed(
+                    BranchCleanerError::RemoteDeleteRefused(format!("{} is protected", name)),
+                )));
+            } else if self.repo.find_reference(&self.remote_ref(name)).is_err() {
+                outcomes.push(Some(DeletionOutcome::Skipped));
+            } else {
+                to_push.push((outcomes.len(), name));
+                outcomes.push(None);
             }
         }
-        Ok(())
+
+        if !to_push.is_empty() {
+            let refspecs: Vec<String> = to_push
+                .iter()
+                .map(|(_, name)| format!(":refs/heads/{}", name))
+                .collect();
+            match self.push(&refspecs) {
+                Ok(rejected) => {
+                    for (index, name) in to_push {
+                        let refname = format!("refs/heads/{}", name);
+                        outcomes[index] = Some(match rejected.get(&refname) {
+                            Some(message) => DeletionOutcome::Failed(
+                                BranchCleanerError::PushFailed(message.clone()),
+                            ),
+                            None => {
+                                self.prune_remote_ref(name);
+                                DeletionOutcome::Deleted
+                            }
+                        });
+                    }
+                }
+                Err(e) => {
+                    let message = e.to_string();
+                    for (index, _) in to_push {
+                        outcomes[index] = Some(DeletionOutcome::Failed(
+                            BranchCleanerError::PushFailed(message.clone()),
+                        ));
+                    }
+                }
+            }
+        }
+
+        names
+            .iter()
+            .zip(outcomes)
+            .map(|(name, outcome)| {
+                DeletionResult::new(name, outcome.unwrap_or(DeletionOutcome::Skipped))
+            })
+            .collect()
+    }
+
+    /// Pushes refspecs to the managed remote in one connection
+    /// Returns the refs the remote rejected, with the remote's message
+    fn push(&self, refspecs: &[String]) -> Result<HashMap<String, String>> {
+        let url = self.remote_url(&self.remote)?;
+        let mut remote = self.repo.remote_anonymous(&url)?;
+        let config = self.repo.config()?;
+
+        let rejected = RefCell::new(HashMap::new());
+        let mut tried = CredentialType::empty();
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(|url, username_from_url, allowed| {
+            // libgit2 keeps asking while authentication fails; give each method one try
+            let username = username_from_url.unwrap_or("git");
+            let method = next_credential_type(allowed, tried)
+                .ok_or_else(|| git2::Error::from_str("authentication failed"))?;
+            tried |= method;
+            match method {
+                CredentialType::USERNAME => Cred::username(username),
+                CredentialType::SSH_KEY => Cred::ssh_key_from_agent(username),
+                CredentialType::USER_PASS_PLAINTEXT => {
+                    Cred::credential_helper(&config, url, username_from_url)
+                }
+                _ => Cred::default(),
+            }
+        });
+        callbacks.push_update_reference(|refname, status| {
+            if let Some(message) = status {
+                rejected
+                    .borrow_mut()
+                    .insert(refname.to_owned(), message.to_owned());
+            }
+            Ok(())
+        });
+
+        let mut options = PushOptions::new();
+        options.remote_callbacks(callbacks);
+        remote.push(refspecs, Some(&mut options))?;
+        drop(options);
+
+        Ok(rejected.into_inner())
     }
 
-    /// Gets the origin remote URL
-    pub fn get_origin_url(&self) -> Result<String> {
+    /// Recreates the most recently deleted batch of branches from the journal
+    /// Returns the names of the restored branches (empty if there is nothing to undo)
+    pub fn restore_last_deletion(&self) -> Result<Vec<String>> {
+        let journal = self.journal();
+        let batch = match journal.last_batch()? {
+            Some(batch) => batch,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut restored = Vec::new();
+        for entry in &batch.branches {
+            // Skip branches that were recreated some other way
+            if self
+                .repo
+                .find_branch(&entry.name, BranchType::Local)
+                .is_ok()
+            {
+                continue;
+            }
+
+            let commit = self.repo.find_commit(Oid::from_str(&entry.tip)?)?;
+            self.repo.branch(&entry.name, &commit, false)?;
+
+            let mut config = self.repo.config()?;
+            if let Some(remote) = &entry.upstream_remote {
+                config.set_str(&format!("branch.{}.remote", entry.name), remote)?;
+            }
+            if let Some(merge) = &entry.upstream_merge {
+                config.set_str(&format!("branch.{}.merge", entry.name), merge)?;
+            }
+
+            restored.push(entry.name.clone());
+        }
+
+        // Only forget the batch once every branch is back
+        journal.pop_last_batch()?;
+        Ok(restored)
+    }
+
+    /// The deletion journal lives in the git directory (`.git/branch-cleaner/`)
+    fn journal(&self) -> DeletionJournal {
+        DeletionJournal::in_git_dir(self.repo.path())
+    }
+
+    /// Journal of branches deleted on GitHub (`.git/branch-cleaner/remote-journal.json`)
+    pub fn remote_journal(&self) -> DeletionJournal {
+        DeletionJournal::remote_in_git_dir(self.repo.path())
+    }
+
+    /// Cache of PR lookups for `owner/repo` (`.git/branch-cleaner/pr-cache.json`)
+    pub fn pr_cache(&self, owner: &str, repo: &str, ttl: Duration) -> PrCache {
+        PrCache::in_git_dir(self.repo.path(), owner, repo, ttl)
+    }
+
+    /// Captures a branch's tip and upstream config before it is deleted
+    fn journal_entry(&self, name: &str, tip: Oid) -> Result<JournalEntry> {
+        let config = self.repo.config()?;
+        Ok(JournalEntry {
+            name: name.to_owned(),
+            tip: tip.to_string(),
+            upstream_remote: config.get_string(&format!("branch.{}.remote", name)).ok(),
+            upstream_merge: config.get_string(&format!("branch.{}.merge", name)).ok(),
+        })
+    }
+
+    /// Gets the URL of the named remote
+    pub fn remote_url(&self, name: &str) -> Result<String> {
         let remote = self
             .repo
-            .find_remote("origin")
-            .map_err(|_| BranchCleanerError::NoOriginRemote)?;
+            .find_remote(name)
+            .map_err(|_| BranchCleanerError::NoRemote(name.to_owned()))?;
 
         let url = remote.url().ok_or_else(|| {
             BranchCleanerError::RemoteParseError("Invalid UTF-8 in remote URL".into())
@@ -94,50 +633,538 @@ impl GitRepository {
 
         Ok(url.to_owned())
     }
+
+    /// The `upstream` remote, when there is one besides the managed remote
+    /// (a fork's `origin` with PRs opened against `upstream`)
+    pub fn upstream_remote(&self) -> Option<&'static str> {
+        (self.remote != UPSTREAM_REMOTE && self.repo.find_remote(UPSTREAM_REMOTE).is_ok())
+            .then_some(UPSTREAM_REMOTE)
+    }
+
+    /// Remote-tracking ref for a branch on the managed remote
+    fn remote_ref(&self, name: &str) -> String {
+        format!("refs/remotes/{}/{}", self.remote, name)
+    }
 }
 
-/// Parses GitHub owner and repo from a git remote URL
+/// A repository on github.com or a GitHub Enterprise Server, as named by a remote URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitHubRemote {
+    /// Host name, e.g. `github.com` or `github.example.com`
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+}
+
+/// Parses the GitHub host, owner and repo from a git remote URL
 /// Supports both SSH (git@github.com:owner/repo.git) and HTTPS formats
-pub fn parse_github_remote(url: &str) -> Result<(String, String)> {
-    use git_url_parse::GitUrl;
+pub fn parse_github_remote(url: &str) -> Result<GitHubRemote> {
     use git_url_parse::types::provider::GenericProvider;
+    use git_url_parse::GitUrl;
 
-    let parsed = GitUrl::parse(url)
-        .map_err(|e| BranchCleanerError::RemoteParseError(e.to_string()))?;
+    let parsed =
+        GitUrl::parse(url).map_err(|e| BranchCleanerError::RemoteParseError(e.to_string()))?;
+
+    // Local paths and file:// URLs have no host to ask for PRs
+    let host = parsed
+        .host()
+        .ok_or_else(|| BranchCleanerError::RemoteParseError(format!("no host in {}", url)))?
+        .to_lowercase();
 
     let provider: GenericProvider = parsed
         .provider_info()
         .map_err(|e| BranchCleanerError::RemoteParseError(e.to_string()))?;
 
-    let owner = provider.owner().to_string();
-    let name = provider.repo().to_string();
+    Ok(GitHubRemote {
+        host,
+        owner: provider.owner().to_string(),
+        repo: provider.repo().to_string(),
+    })
+}
 
-    Ok((owner, name))
+/// The credential method to try next: each allowed one once, in order of preference
+fn next_credential_type(allowed: CredentialType, tried: CredentialType) -> Option<CredentialType> {
+    [
+        CredentialType::USERNAME,
+        CredentialType::SSH_KEY,
+        CredentialType::USER_PASS_PLAINTEXT,
+        CredentialType::DEFAULT,
+    ]
+    .into_iter()
+    .find(|&method| allowed.contains(method) && !tried.contains(method))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Creates a repository with one commit on `main` and the given extra branches
+    fn init_repo_with_branches(dir: &Path, branches: &[&str]) -> GitRepository {
+        let repo = Repository::init(dir).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree_id = repo.index().unwrap().write_tree().unwrap();
+        let tree = repo.find_tree(tree_id).unwrap();
+        let oid = repo
+            .commit(Some("refs/heads/main"), &sig, &sig, "initial", &tree, &[])
+            .unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+
+        let commit = repo.find_commit(oid).unwrap();
+        for name in branches {
+            repo.branch(name, &commit, false).unwrap();
+        }
+        GitRepository::open(dir).unwrap()
+    }
+
+    /// Commits `contents` to `file` on top of `parent`, updating `refname`
+    fn commit_file(
+        git: &GitRepository,
+        refname: &str,
+        parent: Oid,
+        file: &str,
+        contents: &str,
+    ) -> Oid {
+        let repo = &git.repo;
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let parent = repo.find_commit(parent).unwrap();
+        let blob = repo.blob(contents.as_bytes()).unwrap();
+        let mut builder = repo.treebuilder(Some(&parent.tree().unwrap())).unwrap();
+        builder.insert(file, blob, 0o100644).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        repo.commit(Some(refname), &sig, &sig, file, &tree, &[&parent])
+            .unwrap()
+    }
+
+    fn tip(git: &GitRepository, name: &str) -> Oid {
+        git.repo
+            .refname_to_id(&format!("refs/heads/{}", name))
+            .unwrap()
+    }
+
+    #[test]
+    fn every_allowed_credential_method_is_tried_once() {
+        let allowed = CredentialType::SSH_KEY | CredentialType::USER_PASS_PLAINTEXT;
+        let mut tried = CredentialType::empty();
+        let mut order = Vec::new();
+        while let Some(method) = next_credential_type(allowed, tried) {
+            tried |= method;
+            order.push(method);
+        }
+
+        assert_eq!(
+            order,
+            vec![CredentialType::SSH_KEY, CredentialType::USER_PASS_PLAINTEXT]
+        );
+    }
+
+    #[test]
+    fn detects_branch_merged_as_ancestor() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["feature"]);
+        let feature = commit_file(&git, "refs/heads/feature", tip(&git, "main"), "a.txt", "a");
+        // Fast-forward main to the feature commit
+        git.repo
+            .reference("refs/heads/main", feature, true, "test")
+            .unwrap();
+
+        assert_eq!(
+            git.local_merge_status("feature", "main").unwrap(),
+            Some(LocalMerge::Ancestor)
+        );
+    }
+
+    #[test]
+    fn reads_last_commit_details() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["feature"]);
+        let sha = tip(&git, "feature").to_string();
+
+        let commit = git.commit_info(&sha).unwrap();
+
+        assert_eq!(commit.author_name, "Test");
+        assert_eq!(commit.author_email, "test@example.com");
+        assert_eq!(commit.subject, "initial");
+        assert!(commit.time > 0);
+        assert_eq!(
+            git.commit_info("0123456789abcdef0123456789abcdef01234567"),
+            None
+        );
+    }
+
+    #[test]
+    fn counts_commits_made_after_the_pr_was_merged() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["feature"]);
+        let root = tip(&git, "main");
+        let merged_head = commit_file(&git, "refs/heads/feature", root, "a.txt", "a");
+        let later = commit_file(&git, "refs/heads/feature", merged_head, "b.txt", "b");
+        let later = commit_file(&git, "refs/heads/feature", later, "c.txt", "c");
+        let (merged_head, later) = (merged_head.to_string(), later.to_string());
+
+        assert_eq!(
+            git.commits_after(&later, &merged_head, None).unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            git.commits_after(&merged_head, &merged_head, None).unwrap(),
+            Some(0)
+        );
+        // PR head never fetched: count what is not in the base branch yet
+        let unknown = "0123456789abcdef0123456789abcdef01234567";
+        assert_eq!(
+            git.commits_after(&later, unknown, Some("main")).unwrap(),
+            Some(3)
+        );
+        assert_eq!(git.commits_after(&later, unknown, None).unwrap(), None);
+    }
+
+    #[test]
+    fn detects_rebase_merged_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["feature"]);
+        let root = tip(&git, "main");
+        commit_file(&git, "refs/heads/feature", root, "a.txt", "a");
+        // main gets an unrelated commit, then the same change as a different commit
+        let other = commit_file(&git, "refs/heads/main", root, "b.txt", "b");
+        commit_file(&git, "refs/heads/main", other, "a.txt", "a");
+
+        assert_eq!(
+            git.local_merge_status("feature", "main").unwrap(),
+            Some(LocalMerge::RebaseMerged)
+        );
+    }
+
+    #[test]
+    fn detects_squash_merged_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["feature"]);
+        let root = tip(&git, "main");
+        let first = commit_file(&git, "refs/heads/feature", root, "a.txt", "a");
+        commit_file(&git, "refs/heads/feature", first, "b.txt", "b");
+        // main gets both files in a single squashed commit
+        let squashed = {
+            let repo = &git.repo;
+            let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+            let feature_tree = repo
+                .find_commit(tip(&git, "feature"))
+                .unwrap()
+                .tree()
+                .unwrap();
+            let parent = repo.find_commit(root).unwrap();
+            repo.commit(None, &sig, &sig, "squash", &feature_tree, &[&parent])
+                .unwrap()
+        };
+        git.repo
+            .reference("refs/heads/main", squashed, true, "test")
+            .unwrap();
+
+        assert_eq!(
+            git.local_merge_status("feature", "main").unwrap(),
+            Some(LocalMerge::SquashMerged)
+        );
+    }
+
+    #[test]
+    fn unmerged_branch_has_no_local_merge_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["feature"]);
+        commit_file(&git, "refs/heads/feature", tip(&git, "main"), "a.txt", "a");
+
+        assert_eq!(git.local_merge_status("feature", "main").unwrap(), None);
+    }
+
+    #[test]
+    fn reports_upstream_tracking_and_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["tracked", "gone", "untracked"]);
+        let root = tip(&git, "main");
+        git.repo
+            .reference("refs/remotes/origin/tracked", root, false, "test")
+            .unwrap();
+        commit_file(&git, "refs/heads/tracked", root, "a.txt", "a");
+        {
+            let mut config = git.repo.config().unwrap();
+            for name in ["tracked", "gone"] {
+                config
+                    .set_str(&format!("branch.{}.remote", name), "origin")
+                    .unwrap();
+                config
+                    .set_str(
+                        &format!("branch.{}.merge", name),
+                        &format!("refs/heads/{}", name),
+                    )
+                    .unwrap();
+            }
+        }
+
+        assert_eq!(
+            git.upstream_status("tracked").unwrap(),
+            Some(UpstreamStatus::Tracking {
+                name: "origin/tracked".to_owned(),
+                ahead: 1,
+                behind: 0,
+            })
+        );
+        assert_eq!(
+            git.upstream_status("gone").unwrap(),
+            Some(UpstreamStatus::Gone {
+                name: "origin/gone".to_owned()
+            })
+        );
+        assert_eq!(git.upstream_status("untracked").unwrap(), None);
+    }
+
+    #[test]
+    fn list_local_branches_honors_protection_rules() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = crate::config::Config::parse(
+            r#"protect = { names = ["staging"], globs = ["release/*"] }"#,
+        )
+        .unwrap();
+        let git = init_repo_with_branches(
+            dir.path(),
+            &["develop", "staging", "release/1.0", "feature"],
+        )
+        .with_protection(config.protection_rules().unwrap());
+
+        // main is HEAD, develop is built in, the rest come from config
+        assert_eq!(
+            git.list_local_branches().unwrap(),
+            vec!["feature".to_owned()]
+        );
+    }
+
+    #[test]
+    fn default_branch_is_read_from_origin_head_and_protected() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["trunk", "feature"]);
+        let tip = git.repo.refname_to_id("refs/heads/trunk").unwrap();
+        git.repo
+            .reference("refs/remotes/origin/trunk", tip, false, "test")
+            .unwrap();
+        git.repo
+            .reference_symbolic(
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/trunk",
+                false,
+                "test",
+            )
+            .unwrap();
+
+        let default_branch = git.local_default_branch();
+        assert_eq!(default_branch.as_deref(), Some("trunk"));
+
+        let git = git.with_default_branch(default_branch);
+        assert_eq!(
+            git.list_local_branches().unwrap(),
+            vec!["feature".to_owned()]
+        );
+    }
+
+    #[test]
+    fn deleted_branches_can_be_restored_with_upstream() {
+        // Arrange: A branch tracking origin
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["feature", "other"]);
+        {
+            let mut config = git.repo.config().unwrap();
+            config.set_str("branch.feature.remote", "origin").unwrap();
+            config
+                .set_str("branch.feature.merge", "refs/heads/feature")
+                .unwrap();
+        }
+        let tip = git.branch_tip("feature").unwrap();
+
+        // Act: Delete, then undo from a freshly opened repository (new session)
+        let results = git.delete_branches(&["feature".to_owned(), "missing".to_owned()]);
+        assert!(matches!(results[0].outcome, DeletionOutcome::Deleted));
+        assert!(matches!(results[1].outcome, DeletionOutcome::Skipped));
+        assert_eq!(git.list_local_branches().unwrap(), vec!["other".to_owned()]);
+
+        let reopened = GitRepository::open(dir.path()).unwrap();
+        let restored = reopened.restore_last_deletion().unwrap();
+
+        // Assert: Same tip and upstream config as before deletion
+        assert_eq!(restored, vec!["feature".to_owned()]);
+        assert_eq!(reopened.branch_tip("feature").unwrap(), tip);
+        let config = reopened.repo.config().unwrap();
+        assert_eq!(
+            config.get_string("branch.feature.remote").unwrap(),
+            "origin"
+        );
+        assert_eq!(
+            config.get_string("branch.feature.merge").unwrap(),
+            "refs/heads/feature"
+        );
+
+        // Nothing left to undo
+        assert!(reopened.restore_last_deletion().unwrap().is_empty());
+    }
+
+    #[test]
+    fn deletes_remote_branches_on_origin() {
+        // Arrange: A bare origin with the branches pushed to it
+        let origin_dir = tempfile::tempdir().unwrap();
+        Repository::init_bare(origin_dir.path()).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &["feature", "local-only"])
+            .with_protection(ProtectionRules::default());
+        let url = origin_dir.path().to_str().unwrap();
+        git.repo.remote("origin", url).unwrap();
+        let mut origin = git.repo.find_remote("origin").unwrap();
+        origin
+            .push(
+                &[
+                    "refs/heads/main:refs/heads/main",
+                    "refs/heads/feature:refs/heads/feature",
+                ],
+                None,
+            )
+            .unwrap();
+        origin
+            .fetch(&["refs/heads/*:refs/remotes/origin/*"], None, None)
+            .unwrap();
+
+        // Act
+        let results = git.delete_remote_branches(&[
+            "feature".to_owned(),
+            "local-only".to_owned(),
+            "main".to_owned(),
+        ]);
+
+        // Assert: Only the unprotected branch that exists on origin is deleted
+        assert!(matches!(results[0].outcome, DeletionOutcome::Deleted));
+        assert!(matches!(results[1].outcome, DeletionOutcome::Skipped));
+        assert!(matches!(
+            results[2].outcome,
+            DeletionOutcome::Failed(BranchCleanerError::RemoteDeleteRefused(_))
+        ));
+        let bare = Repository::open_bare(origin_dir.path()).unwrap();
+        assert!(bare.find_reference("refs/heads/feature").is_err());
+        assert!(bare.find_reference("refs/heads/main").is_ok());
+        assert!(git
+            .repo
+            .find_reference("refs/remotes/origin/feature")
+            .is_err());
+        // The local branch is untouched
+        assert!(git.branch_tip("feature").unwrap().is_some());
+    }
+
+    #[test]
+    fn lists_remote_branches_with_merge_status() {
+        // Arrange: origin has a merged branch, an unmerged one and the protected default
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &[]);
+        let root = tip(&git, "main");
+        let unmerged = commit_file(&git, "refs/remotes/origin/unmerged", root, "a.txt", "a");
+        git.repo
+            .reference("refs/remotes/origin/main", root, false, "test")
+            .unwrap();
+        git.repo
+            .reference("refs/remotes/origin/merged", root, false, "test")
+            .unwrap();
+        git.repo
+            .reference_symbolic(
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/main",
+                false,
+                "test",
+            )
+            .unwrap();
+
+        // Act
+        let names = git.list_remote_branches().unwrap();
+
+        // Assert
+        assert_eq!(names, vec!["merged".to_owned(), "unmerged".to_owned()]);
+        assert_eq!(
+            git.remote_branch_tip("unmerged"),
+            Some(unmerged.to_string())
+        );
+        assert_eq!(
+            git.remote_merge_status("merged", "main").unwrap(),
+            Some(LocalMerge::Ancestor)
+        );
+        assert_eq!(git.remote_merge_status("unmerged", "main").unwrap(), None);
+    }
+
+    #[test]
+    fn uses_the_selected_remote_and_upstream_as_base() {
+        // Arrange: A fork remote whose stale main lags behind upstream, where the branch was merged
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo_with_branches(dir.path(), &[]);
+        git.repo
+            .remote("fork", "git@github.com:me/repo.git")
+            .unwrap();
+        git.repo
+            .remote("upstream", "git@github.com:owner/repo.git")
+            .unwrap();
+        let root = tip(&git, "main");
+        let feature = commit_file(&git, "refs/remotes/fork/feature", root, "a.txt", "a");
+        git.repo
+            .reference("refs/remotes/fork/main", root, false, "test")
+            .unwrap();
+        commit_file(&git, "refs/remotes/upstream/main", feature, "b.txt", "b");
+
+        // Act
+        let git = git.with_remote("fork");
+
+        // Assert
+        assert_eq!(
+            git.list_remote_branches().unwrap(),
+            vec!["feature".to_owned()]
+        );
+        assert_eq!(
+            git.remote_url(git.remote()).unwrap(),
+            "git@github.com:me/repo.git"
+        );
+        assert_eq!(git.upstream_remote(), Some(UPSTREAM_REMOTE));
+        assert_eq!(
+            git.remote_merge_status("feature", "main").unwrap(),
+            Some(LocalMerge::Ancestor)
+        );
+        assert_eq!(git.clone().remote(), "fork");
+        assert_eq!(git.clone().with_remote("upstream").upstream_remote(), None);
+        assert!(matches!(
+            git.remote_url("origin"),
+            Err(BranchCleanerError::NoRemote(name)) if name == "origin"
+        ));
+    }
+
     #[test]
     fn can_parse_ssh_github_url() {
-        let (owner, repo) = parse_github_remote("git@github.com:owner/repo.git").unwrap();
-        assert_eq!(owner, "owner");
-        assert_eq!(repo, "repo");
+        let remote = parse_github_remote("git@github.com:owner/repo.git").unwrap();
+        assert_eq!(remote.host, "github.com");
+        assert_eq!(remote.owner, "owner");
+        assert_eq!(remote.repo, "repo");
     }
 
     #[test]
     fn can_parse_https_github_url() {
-        let (owner, repo) = parse_github_remote("https://github.com/owner/repo").unwrap();
-        assert_eq!(owner, "owner");
-        assert_eq!(repo, "repo");
+        let remote = parse_github_remote("https://github.com/owner/repo").unwrap();
+        assert_eq!(remote.owner, "owner");
+        assert_eq!(remote.repo, "repo");
     }
 
     #[test]
     fn can_parse_https_github_url_with_git_extension() {
-        let (owner, repo) = parse_github_remote("https://github.com/owner/repo.git").unwrap();
-        assert_eq!(owner, "owner");
-        assert_eq!(repo, "repo");
+        let remote = parse_github_remote("https://github.com/owner/repo.git").unwrap();
+        assert_eq!(remote.owner, "owner");
+        assert_eq!(remote.repo, "repo");
+    }
+
+    #[test]
+    fn can_parse_enterprise_urls() {
+        let ssh = parse_github_remote("git@GitHub.Example.com:team/service.git").unwrap();
+        let https = parse_github_remote("https://github.example.com/team/service").unwrap();
+
+        let expected = GitHubRemote {
+            host: "github.example.com".to_owned(),
+            owner: "team".to_owned(),
+            repo: "service".to_owned(),
+        };
+        assert_eq!(ssh, expected);
+        assert_eq!(https, expected);
     }
 
     #[test]
@@ -1,124 +1,884 @@
+use futures::stream::{self, StreamExt};
 use octocrab::{params, Octocrab};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::Semaphore;
 
+use crate::auth::{discover_token, Token, TokenSource, GITHUB_COM};
+use crate::config::HostConfig;
 use crate::error::{BranchCleanerError, Result};
-use crate::{BCBranch, PrStatus};
+use crate::pr_cache::{CacheLookup, PrCache};
+use crate::store::RateLimit;
+use crate::{unix_now, BCBranch, PrStatus, PrSummary};
+
+/// Attempts per request, including the first one
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Longest we wait for an exhausted quota to reset before failing the request
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Longest a single request spends waiting across all of its retries
+const MAX_REQUEST_WAIT: Duration = Duration::from_secs(90);
+
+/// GitHub asks clients to wait at least a minute after a secondary rate limit
+/// when the response has no `retry-after` header
+const SECONDARY_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// Number of branches looked up per GraphQL request (one aliased field each)
+const GRAPHQL_BATCH_SIZE: usize = 50;
+
+/// PRs fetched per branch in a GraphQL batch; branches with more are paged through over REST
+const GRAPHQL_PRS_PER_BRANCH: usize = 20;
+
+/// Page size of REST `pulls` listings (GitHub's maximum)
+const REST_PAGE_SIZE: usize = 100;
+
+/// Requests to GitHub in flight at once, unless configured otherwise
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// A GitHub instance (github.com or a GitHub Enterprise Server) and how to reach its API
+#[derive(Debug, Clone, PartialEq)]
+pub struct GitHubHost {
+    name: String,
+    api_url: String,
+    token: Option<String>,
+}
+
+impl GitHubHost {
+    /// github.com serves its API at `https://api.github.com`,
+    /// Enterprise Servers at `https://<host>/api/v3`
+    pub fn new(name: &str) -> Self {
+        let api_url = if name == GITHUB_COM {
+            "https://api.github.com".to_string()
+        } else {
+            format!("https://{}/api/v3", name)
+        };
+        Self {
+            name: name.to_owned(),
+            api_url,
+            token: None,
+        }
+    }
+
+    /// Applies the host's config: a custom API base URL and token
+    pub fn with_config(mut self, config: &HostConfig) -> Self {
+        if let Some(api_url) = &config.api_url {
+            self.api_url = api_url.trim_end_matches('/').to_owned();
+        }
+        if config.token.is_some() {
+            self.token = config.token.clone();
+        }
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Finds a token for the host (see `auth::discover_token`)
+    fn token(&self) -> Option<Token> {
+        discover_token(&self.name, self.token.as_deref())
+    }
+
+    /// GraphQL is served next to the REST API: `https://api.github.com/graphql`,
+    /// `https://<host>/api/graphql`
+    fn graphql_base(&self) -> &str {
+        self.api_url.strip_suffix("/v3").unwrap_or(&self.api_url)
+    }
+}
+
+/// Data returned by the batched PR query: one connection per `b<index>` alias
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    repository: Option<HashMap<String, PrConnection>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrConnection {
+    /// PRs with this head ref name, from every fork: may exceed `nodes`
+    total_count: usize,
+    nodes: Vec<PrNode>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrNode {
+    number: u32,
+    title: String,
+    /// `OPEN`, `CLOSED` or `MERGED`
+    state: String,
+    closed_at: Option<String>,
+    head_ref_oid: Option<String>,
+    head_repository_owner: Option<RepositoryOwner>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryOwner {
+    login: String,
+}
+
+/// GraphQL responses carry errors in the body, usually with a 200 status
+#[derive(Debug, Deserialize)]
+struct GraphqlBody<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphqlError {
+    message: String,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+/// A request that can be sent again when GitHub asks us to retry
+enum Request<'a> {
+    Get(String),
+    /// Conditional GET: GitHub answers 304 (free of quota) while the ETag still matches
+    GetIfNoneMatch(String, &'a str),
+    /// POST of a query to the GraphQL endpoint
+    Graphql(&'a serde_json::Value),
+}
+
+/// A successful (2xx or 304) response
+struct Fetched {
+    status: u16,
+    etag: Option<String>,
+    body: String,
+}
+
+/// Result of revalidating a cached PR lookup
+#[derive(Debug, PartialEq)]
+pub enum Revalidated {
+    /// GitHub answered 304 Not Modified: the cached result still holds
+    Unchanged,
+    /// The PRs changed (or were never fetched over REST); comes with the new ETag
+    Changed {
+        prs: Vec<PrSummary>,
+        etag: Option<String>,
+    },
+}
 
 /// GitHubClient wraps octocrab with higher-level operations
 #[derive(Clone)]
 pub struct GitHubClient {
     octocrab: Octocrab,
+    // Same token, rooted where the host serves GraphQL
+    graphql_octocrab: Octocrab,
+    // Repository PRs are opened against
     owner: String,
     repo: String,
+    // Repository the branches live in: a fork of `owner/repo`, or the same repository
+    head_owner: String,
+    head_repo: String,
+    offline: bool,
+    concurrency: usize,
+    // Shared by clones so the limit holds across every lookup
+    limiter: Arc<Semaphore>,
+    // Quota reported by the most recent response
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
+    // Where the token came from (`None` when offline)
+    token_source: Option<TokenSource>,
 }
 
 impl GitHubClient {
-    /// Creates a new client from environment token
-    pub fn from_env(owner: String, repo: String) -> Result<Self> {
-        let token =
-            std::env::var("GITHUB_TOKEN").map_err(|_| BranchCleanerError::TokenNotFound)?;
+    /// Creates a client for the host with the first token found (see `auth::discover_token`)
+    pub fn connect(host: &GitHubHost, owner: String, repo: String) -> Result<Self> {
+        let token = host.token().ok_or(BranchCleanerError::TokenNotFound)?;
+        Self::new(host, owner, repo, token)
+    }
 
-        let octocrab = Octocrab::builder().personal_token(token).build()?;
+    /// Creates a client for the host's API authenticated with `token`
+    pub fn new(host: &GitHubHost, owner: String, repo: String, token: Token) -> Result<Self> {
+        let octocrab = Octocrab::builder()
+            .base_uri(host.api_url.as_str())?
+            .personal_token(token.value.clone())
+            .build()?;
+        let graphql_octocrab = Octocrab::builder()
+            .base_uri(host.graphql_base())?
+            .personal_token(token.value)
+            .build()?;
 
         Ok(Self {
             octocrab,
+            graphql_octocrab,
+            head_owner: owner.clone(),
+            head_repo: repo.clone(),
             owner,
             repo,
+            offline: false,
+            concurrency: DEFAULT_CONCURRENCY,
+            limiter: Arc::new(Semaphore::new(DEFAULT_CONCURRENCY)),
+            rate_limit: Arc::new(Mutex::new(None)),
+            token_source: Some(token.source),
         })
     }
 
-    /// Creates a client that works offline (marks all as NONE)
+    /// Creates a client that works offline (marks all as UNKNOWN, makes no requests)
     pub fn offline(owner: String, repo: String) -> Self {
-        // Use default octocrab (no auth) - will fail gracefully
+        // Default octocrab (no auth) is never used while offline
         let octocrab = Octocrab::default();
         Self {
+            graphql_octocrab: octocrab.clone(),
             octocrab,
+            head_owner: owner.clone(),
+            head_repo: repo.clone(),
             owner,
             repo,
+            offline: true,
+            concurrency: DEFAULT_CONCURRENCY,
+            limiter: Arc::new(Semaphore::new(DEFAULT_CONCURRENCY)),
+            rate_limit: Arc::new(Mutex::new(None)),
+            token_source: None,
         }
     }
 
-    /// Fetches PR info for a branch name, returns (status, number, title)
+    /// Limits how many PR lookups run at the same time (at least one)
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self.limiter = Arc::new(Semaphore::new(self.concurrency));
+        self
+    }
+
+    /// Sets the repository the branches live in, when it is a fork of the one PRs are opened against
+    /// PRs are then looked up with the fork owner as head prefix (`fork-owner:branch`)
+    pub fn with_head_repo(mut self, owner: String, repo: String) -> Self {
+        self.head_owner = owner;
+        self.head_repo = repo;
+        self
+    }
+
+    /// Whether the client makes no requests (no token was found)
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Where the token came from (`None` for offline clients)
+    pub fn token_source(&self) -> Option<TokenSource> {
+        self.token_source
+    }
+
+    /// Remaining API quota reported by the most recent response
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    /// Sends a request and parses its JSON body
+    async fn send<T: DeserializeOwned>(&self, request: Request<'_>) -> Result<T> {
+        let fetched = self.fetch(request).await?;
+        Ok(serde_json::from_str(&fetched.body)?)
+    }
+
+    /// Sends a request, retrying connection failures, server errors and rate limits with backoff,
+    /// and records the quota from the response headers
+    async fn fetch(&self, request: Request<'_>) -> Result<Fetched> {
+        let mut attempt = 1;
+        let mut waited = Duration::ZERO;
+        loop {
+            let sent = match &request {
+                Request::Get(route) => self.octocrab._get(route.as_str()).await,
+                Request::GetIfNoneMatch(route, etag) => {
+                    let mut headers = http::HeaderMap::new();
+                    if let Ok(value) = http::HeaderValue::from_str(etag) {
+                        headers.insert(http::header::IF_NONE_MATCH, value);
+                    }
+                    self.octocrab
+                        ._get_with_headers(route.as_str(), Some(headers))
+                        .await
+                }
+                Request::Graphql(body) => {
+                    self.graphql_octocrab._post("/graphql", Some(*body)).await
+                }
+            };
+            let response = match sent {
+                Ok(response) => response,
+                // Connection problems and timeouts are worth another try
+                Err(e) if is_transient(&e) && may_retry(attempt, waited, backoff(attempt)) => {
+                    tokio::time::sleep(backoff(attempt)).await;
+                    waited += backoff(attempt);
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+
+            let status = response.status().as_u16();
+            let rate_limit = rate_limit_from_headers(response.headers());
+            if rate_limit.is_some() {
+                *self.rate_limit.lock().unwrap() = rate_limit;
+            }
+            let retry_after = response
+                .headers()
+                .get("retry-after")
+                .and_then(|value| value.to_str().ok()?.parse().ok());
+            let etag = response
+                .headers()
+                .get(http::header::ETAG)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_owned());
+            let body = self.octocrab.body_to_string(response).await?;
+
+            if (200..300).contains(&status) || status == 304 {
+                return Ok(Fetched { status, etag, body });
+            }
+
+            let now = unix_now();
+            match retry_delay(status, rate_limit, retry_after, &body, attempt, now) {
+                Some(delay) if may_retry(attempt, waited, delay) => {
+                    tokio::time::sleep(delay).await;
+                    waited += delay;
+                    attempt += 1;
+                }
+                _ => {
+                    return Err(match (rate_limit, retry_after) {
+                        (Some(limit), _) if limit.remaining == 0 => {
+                            BranchCleanerError::RateLimited(limit.reset.saturating_sub(now))
+                        }
+                        (_, Some(seconds)) if matches!(status, 403 | 429) => {
+                            BranchCleanerError::RateLimited(seconds)
+                        }
+                        _ => BranchCleanerError::GitHubStatus(status, error_message(&body)),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Runs a GraphQL query, treating errors in the response body as failures
+    /// GraphQL reports an exhausted quota as a `RATE_LIMITED` error; it is retried once it resets
+    async fn graphql<T: DeserializeOwned>(&self, payload: &serde_json::Value) -> Result<T> {
+        let mut attempt = 1;
+        let mut waited = Duration::ZERO;
+        loop {
+            let body: GraphqlBody<T> = self.send(Request::Graphql(payload)).await?;
+            let rate_limited = body
+                .errors
+                .iter()
+                .any(|e| e.kind.as_deref() == Some("RATE_LIMITED"));
+            if let (Some(data), true) = (body.data, body.errors.is_empty()) {
+                return Ok(data);
+            }
+
+            if rate_limited {
+                let now = unix_now();
+                let wait = self
+                    .rate_limit()
+                    .map(|limit| Duration::from_secs(limit.reset.saturating_sub(now).max(1)))
+                    .unwrap_or(SECONDARY_LIMIT_WAIT);
+                if wait <= MAX_RATE_LIMIT_WAIT && may_retry(attempt, waited, wait) {
+                    tokio::time::sleep(wait).await;
+                    waited += wait;
+                    attempt += 1;
+                    continue;
+                }
+                return Err(BranchCleanerError::RateLimited(wait.as_secs()));
+            }
+
+            let message = body
+                .errors
+                .into_iter()
+                .map(|e| e.message)
+                .collect::<Vec<_>>()
+                .join("; ");
+            return Err(BranchCleanerError::GraphQl(message));
+        }
+    }
+
+    /// Fetches the default branch (e.g. `main`, `trunk`) of the repository PRs are opened against
+    pub async fn default_branch(&self) -> Result<Option<String>> {
+        if self.offline {
+            return Ok(None);
+        }
+        let repo = self.octocrab.repos(&self.owner, &self.repo).get().await?;
+        Ok(repo.default_branch)
+    }
+
+    /// Lists the head repository's branches as (name, tip SHA), skipping branches GitHub protects
+    /// Offline clients return `None` so callers can fall back to remote-tracking refs
+    pub async fn list_branches(&self) -> Result<Option<Vec<(String, String)>>> {
+        if self.offline {
+            return Ok(None);
+        }
+        let first_page = self
+            .octocrab
+            .repos(&self.head_owner, &self.head_repo)
+            .list_branches()
+            .per_page(100)
+            .send()
+            .await?;
+        let branches = self.octocrab.all_pages(first_page).await?;
+        Ok(Some(
+            branches
+                .into_iter()
+                .filter(|b| !b.protected)
+                .map(|b| (b.name, b.commit.sha))
+                .collect(),
+        ))
+    }
+
+    /// Deletes a branch on GitHub through the git refs API
+    pub async fn delete_branch(&self, name: &str) -> Result<()> {
+        if self.offline {
+            return Err(BranchCleanerError::TokenNotFound);
+        }
+        self.octocrab
+            .repos(&self.head_owner, &self.head_repo)
+            .delete_ref(&params::repos::Reference::Branch(name.to_owned()))
+            .await?;
+        Ok(())
+    }
+
+    /// Recreates a branch on GitHub pointing at `sha` (undo of `delete_branch`)
+    pub async fn create_branch(&self, name: &str, sha: &str) -> Result<()> {
+        if self.offline {
+            return Err(BranchCleanerError::TokenNotFound);
+        }
+        self.octocrab
+            .repos(&self.head_owner, &self.head_repo)
+            .create_ref(&params::repos::Reference::Branch(name.to_owned()), sha)
+            .await?;
+        Ok(())
+    }
+
+    /// Fetches every PR opened from a branch, newest first
     /// Only finds PRs where this branch is the SOURCE (head), not the target (base)
-    pub async fn get_pr_for_branch(
+    pub async fn get_prs_for_branch(&self, branch_name: &str) -> Result<Vec<PrSummary>> {
+        if self.offline {
+            return Ok(Vec::new());
+        }
+        let _permit = self.limiter.acquire().await.ok();
+        Ok(self
+            .rest_prs(branch_name, None)
+            .await?
+            .map(|(prs, _)| prs)
+            .unwrap_or_default())
+    }
+
+    /// Looks a branch's PRs up again over REST, sending the cached ETag if there is one
+    /// A 304 answer does not count against the rate limit
+    pub async fn revalidate_prs(
         &self,
         branch_name: &str,
-    ) -> Result<Option<(PrStatus, u32, String)>> {
-        // Try both formats: plain branch name (same-repo PRs) and owner:branch (fork PRs)
-        let head_formats = [
-            branch_name.to_string(),                           // "feature-branch"
-            format!("{}:{}", self.owner, branch_name),          // "owner:feature-branch"
-        ];
+        etag: Option<&str>,
+    ) -> Result<Revalidated> {
+        if self.offline {
+            return Ok(Revalidated::Unchanged);
+        }
+        let _permit = self.limiter.acquire().await.ok();
+        Ok(match self.rest_prs(branch_name, etag).await? {
+            Some((prs, etag)) => Revalidated::Changed { prs, etag },
+            None => Revalidated::Unchanged,
+        })
+    }
 
-        for head_format in &head_formats {
-            let result = self
-                .octocrab
-                .pulls(&self.owner, &self.repo)
-                .list()
-                .head(head_format)
-                .state(params::State::All) // Get both open and closed PRs
-                .per_page(1)
-                .send()
-                .await;
-
-            if let Ok(page) = result {
-                if let Some(pr) = page.items.first() {
-                    // Verify this PR actually has our branch as the head (source)
-                    if pr.head.ref_field.as_str() == branch_name {
-                        let status = if pr.merged_at.is_some() {
-                            PrStatus::MERGED
-                        } else {
-                            match &pr.state {
-                                Some(state) if matches!(state, octocrab::models::IssueState::Open) => {
-                                    PrStatus::OPEN
-                                }
-                                Some(state) if matches!(state, octocrab::models::IssueState::Closed) => {
-                                    PrStatus::CLOSED
-                                }
-                                _ => PrStatus::NONE,
-                            }
-                        };
-
-                        let title = pr.title.clone().unwrap_or_default();
-                        let number = pr.number as u32;
-                        return Ok(Some((status, number, title)));
-                    }
-                }
+    /// Pages through `pulls?head=<head owner>:<branch>` on the repository PRs are opened against
+    /// Returns `None` when the first page still matches `etag`, else the PRs and the first page's ETag
+    async fn rest_prs(
+        &self,
+        branch_name: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<(Vec<PrSummary>, Option<String>)>> {
+        let head = percent_encode(&format!("{}:{}", self.head_owner, branch_name));
+        let mut prs = Vec::new();
+        let mut first_etag = None;
+        for page in 1.. {
+            let route = format!(
+                "/repos/{}/{}/pulls?head={}&state=all&per_page={}&page={}",
+                self.owner, self.repo, head, REST_PAGE_SIZE, page
+            );
+            let fetched = match etag.filter(|_| page == 1) {
+                Some(etag) => self.fetch(Request::GetIfNoneMatch(route, etag)).await?,
+                None => self.fetch(Request::Get(route)).await?,
+            };
+            if fetched.status == 304 {
+                return Ok(None);
+            }
+            if page == 1 {
+                first_etag = fetched.etag;
+            }
+
+            let items: Vec<octocrab::models::pulls::PullRequest> =
+                serde_json::from_str(&fetched.body)?;
+            prs.extend(summaries_from_page(&items, branch_name));
+            if items.len() < REST_PAGE_SIZE {
+                break;
             }
         }
+        Ok(Some((prs, first_etag)))
+    }
+
+    /// Fetches the PRs of each branch name with one GraphQL request
+    /// Returns one list per name, in the same order (empty when the branch has no PR).
+    /// Head ref names shared with too many forks' PRs are paged through over REST instead
+    pub async fn get_prs_for_branches(
+        &self,
+        branch_names: &[String],
+    ) -> Result<Vec<Vec<PrSummary>>> {
+        if self.offline {
+            return Ok(vec![Vec::new(); branch_names.len()]);
+        }
+
+        let mut variables = serde_json::Map::new();
+        variables.insert("owner".into(), self.owner.clone().into());
+        variables.insert("repo".into(), self.repo.clone().into());
+        for (index, name) in branch_names.iter().enumerate() {
+            variables.insert(format!("h{}", index), name.clone().into());
+        }
+        let payload = serde_json::json!({
+            "query": batch_query(branch_names.len()),
+            "variables": variables,
+        });
+
+        let response: BatchResponse = {
+            let _permit = self.limiter.acquire().await.ok();
+            self.graphql(&payload).await?
+        };
+        let mut connections = response.repository.unwrap_or_default();
+        let mut prs = Vec::with_capacity(branch_names.len());
+        for (index, name) in branch_names.iter().enumerate() {
+            prs.push(match connections.remove(&format!("b{}", index)) {
+                Some(connection) if connection.total_count > connection.nodes.len() => {
+                    self.get_prs_for_branch(name).await?
+                }
+                Some(connection) => self.pick_prs(connection),
+                None => Vec::new(),
+            });
+        }
+        Ok(prs)
+    }
 
-        Ok(None) // No PR found with this branch as source
+    /// Keeps the PRs opened from the head repository (not from other forks with the same branch name)
+    fn pick_prs(&self, connection: PrConnection) -> Vec<PrSummary> {
+        connection
+            .nodes
+            .into_iter()
+            .filter(|pr| {
+                pr.head_repository_owner
+                    .as_ref()
+                    .is_some_and(|owner| owner.login.eq_ignore_ascii_case(&self.head_owner))
+            })
+            .map(|pr| PrSummary {
+                number: pr.number,
+                title: pr.title,
+                status: graphql_pr_status(&pr.state),
+                closed_at: pr.closed_at,
+                head_sha: pr.head_ref_oid,
+            })
+            .collect()
     }
 
-    /// Enriches branch names with PR information, streaming each result as it's ready
+    /// Enriches branches with PR information, streaming each result as it's ready
+    /// Branches are looked up in GraphQL batches, several at once (see `with_concurrency`);
+    /// a batch whose query fails falls back to one REST lookup per branch.
+    /// The returned branches keep their input order, whatever order results arrive in.
+    /// Fields filled in from git (tip SHA, upstream) are carried through unchanged
     pub async fn enrich_branches_streaming(
         &self,
-        branch_names: Vec<String>,
-        tx: tokio::sync::mpsc::UnboundedSender<BCBranch>,
+        branches: Vec<BCBranch>,
+        tx: UnboundedSender<BCBranch>,
+    ) -> Vec<BCBranch> {
+        let indexed: Vec<(usize, BCBranch)> = branches.into_iter().enumerate().collect();
+        let batches: Vec<Vec<(usize, BCBranch)>> = indexed
+            .chunks(GRAPHQL_BATCH_SIZE)
+            .map(|batch| batch.to_vec())
+            .collect();
+
+        let tx = &tx;
+        let mut enriched: Vec<(usize, BCBranch)> = stream::iter(batches)
+            .map(|batch| self.enrich_batch(batch, tx))
+            .buffer_unordered(self.concurrency)
+            .concat()
+            .await;
+
+        enriched.sort_by_key(|(index, _)| *index);
+        enriched.into_iter().map(|(_, branch)| branch).collect()
+    }
+
+    /// Enriches branches like `enrich_branches_streaming`, answering from the on-disk cache where it can
+    /// Fresh entries are sent straight away, stale ones are revalidated with conditional
+    /// requests, and only the rest are looked up. The cache is updated with every result.
+    /// Offline, stale entries keep their last seen PRs for display but their status is UNKNOWN,
+    /// and nothing is cached
+    pub async fn enrich_branches_cached(
+        &self,
+        branches: Vec<BCBranch>,
+        cache: &mut PrCache,
+        tx: UnboundedSender<BCBranch>,
     ) -> Vec<BCBranch> {
-        let mut branches = Vec::new();
-
-        for name in branch_names {
-            let branch = match self.get_pr_for_branch(&name).await {
-                Ok(Some((status, number, title))) => BCBranch::with_pr(&name, status, number, &title),
-                Ok(None) | Err(_) => {
-                    // No PR found or API error - mark as NONE
-                    BCBranch::new(&name, PrStatus::NONE)
+        let now = unix_now();
+        let mut enriched = Vec::new();
+        let mut stale = Vec::new();
+        let mut misses = Vec::new();
+        for (index, branch) in branches.into_iter().enumerate() {
+            match cache.lookup(&branch, now) {
+                CacheLookup::Fresh(cached) => {
+                    enriched.push((index, send_with_pr(branch, Ok(cached.prs), &tx)))
+                }
+                CacheLookup::Stale(cached) if self.offline => {
+                    // The PR may have been reopened (or one opened) since it was last seen
+                    let branch = BCBranch {
+                        pr_status: PrStatus::UNKNOWN,
+                        ..branch.with_prs(cached.prs)
+                    };
+                    let _ = tx.send(branch.clone());
+                    enriched.push((index, branch))
+                }
+                CacheLookup::Stale(cached) => stale.push((index, branch, cached)),
+                CacheLookup::Miss => misses.push((index, branch)),
+            }
+        }
+
+        let tx_ref = &tx;
+        let revalidated: Vec<_> = stream::iter(stale)
+            .map(|(index, branch, cached)| async move {
+                let result = self
+                    .revalidate_prs(&branch.name, cached.etag.as_deref())
+                    .await;
+                (index, branch, cached, result)
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+        for (index, branch, cached, result) in revalidated {
+            let branch = match result {
+                Ok(Revalidated::Unchanged) => {
+                    cache.touch(&branch.name, now);
+                    send_with_pr(branch, Ok(cached.prs), tx_ref)
                 }
+                Ok(Revalidated::Changed { prs, etag }) => {
+                    let branch = send_with_pr(branch, Ok(prs), tx_ref);
+                    cache.record(&branch, etag, now);
+                    branch
+                }
+                Err(e) => send_with_pr(branch, Err(e), tx_ref),
             };
+            enriched.push((index, branch));
+        }
 
-            // Send immediately to TUI (ignore error if receiver dropped)
-            let _ = tx.send(branch.clone());
+        let (indexes, to_look_up): (Vec<usize>, Vec<BCBranch>) = misses.into_iter().unzip();
+        let looked_up = self.enrich_branches_streaming(to_look_up, tx).await;
+        for (index, branch) in indexes.into_iter().zip(looked_up) {
+            // Offline lookups find nothing; caching that would hide real PRs on the next run
+            if !self.offline {
+                cache.record(&branch, None, now);
+            }
+            enriched.push((index, branch));
+        }
 
-            branches.push(branch);
+        enriched.sort_by_key(|(index, _)| *index);
+        enriched.into_iter().map(|(_, branch)| branch).collect()
+    }
+
+    /// Enriches one batch, sending each branch as soon as its PR is known
+    async fn enrich_batch(
+        &self,
+        batch: Vec<(usize, BCBranch)>,
+        tx: &UnboundedSender<BCBranch>,
+    ) -> Vec<(usize, BCBranch)> {
+        // Nothing is looked up offline: the branches could have any PR, even an open one
+        if self.offline {
+            return batch
+                .into_iter()
+                .map(|(index, branch)| {
+                    let branch = BCBranch {
+                        pr_status: PrStatus::UNKNOWN,
+                        ..branch
+                    };
+                    let _ = tx.send(branch.clone());
+                    (index, branch)
+                })
+                .collect();
         }
 
-        branches
+        let names: Vec<String> = batch.iter().map(|(_, b)| b.name.clone()).collect();
+        match self.get_prs_for_branches(&names).await {
+            Ok(prs) => batch
+                .into_iter()
+                .zip(prs)
+                .map(|((index, branch), pr)| (index, send_with_pr(branch, Ok(pr), tx)))
+                .collect(),
+            Err(_) => {
+                stream::iter(batch)
+                    .map(|(index, branch)| async move {
+                        let prs = self.get_prs_for_branch(&branch.name).await;
+                        (index, send_with_pr(branch, prs, tx))
+                    })
+                    .buffer_unordered(self.concurrency)
+                    .collect()
+                    .await
+            }
+        }
+    }
+}
+
+/// Fills in the PR fields from the branch's PRs (NONE when there are none, ERROR when
+/// the lookup failed) and sends the branch to the TUI (ignoring the error if the receiver dropped)
+fn send_with_pr(
+    branch: BCBranch,
+    prs: Result<Vec<PrSummary>>,
+    tx: &UnboundedSender<BCBranch>,
+) -> BCBranch {
+    let branch = match prs {
+        Ok(prs) => branch.with_prs(prs),
+        // Never NONE: a branch that merely looks PR-less could be auto-selected
+        Err(e) => BCBranch {
+            pr_status: PrStatus::ERROR(e.to_string()),
+            ..branch
+        },
+    };
+    let _ = tx.send(branch.clone());
+    branch
+}
+
+/// Keeps the PRs from a REST `pulls?head=` page whose head (source) is the branch
+fn summaries_from_page(
+    page: &[octocrab::models::pulls::PullRequest],
+    branch_name: &str,
+) -> Vec<PrSummary> {
+    page.iter()
+        .filter(|pr| pr.head.ref_field.as_str() == branch_name)
+        .map(|pr| {
+            let status = if pr.merged_at.is_some() {
+                PrStatus::MERGED
+            } else {
+                match &pr.state {
+                    Some(octocrab::models::IssueState::Open) => PrStatus::OPEN,
+                    Some(octocrab::models::IssueState::Closed) => PrStatus::CLOSED,
+                    _ => PrStatus::NONE,
+                }
+            };
+            PrSummary {
+                number: pr.number as u32,
+                title: pr.title.clone().unwrap_or_default(),
+                status,
+                closed_at: pr.closed_at.map(|at| at.to_rfc3339()),
+                head_sha: Some(pr.head.sha.clone()),
+            }
+        })
+        .collect()
+}
+
+/// Builds a query with one aliased `pullRequests` field per branch (`b0`, `b1`, ...)
+/// Branch names are passed as variables (`$h0`, `$h1`, ...) so they need no escaping
+fn batch_query(count: usize) -> String {
+    let mut params = String::from("$owner: String!, $repo: String!");
+    let mut fields = String::new();
+    for index in 0..count {
+        params.push_str(&format!(", $h{}: String!", index));
+        fields.push_str(&format!(
+            "b{index}: pullRequests(headRefName: $h{index}, first: {GRAPHQL_PRS_PER_BRANCH}, \
+             orderBy: {{field: CREATED_AT, direction: DESC}}) \
+             {{ totalCount nodes {{ number title state closedAt headRefOid headRepositoryOwner {{ login }} }} }}\n"
+        ));
+    }
+    format!(
+        "query({}) {{ repository(owner: $owner, name: $repo) {{\n{}}} }}",
+        params, fields
+    )
+}
+
+/// Reads the `x-ratelimit-*` headers GitHub sends with every API response
+fn rate_limit_from_headers(headers: &http::HeaderMap) -> Option<RateLimit> {
+    let number = |name: &str| -> Option<u64> { headers.get(name)?.to_str().ok()?.parse().ok() };
+    Some(RateLimit {
+        limit: number("x-ratelimit-limit")?,
+        remaining: number("x-ratelimit-remaining")?,
+        reset: number("x-ratelimit-reset")?,
+    })
+}
+
+/// How long to wait before retrying a failed response, or `None` if it should not be retried
+/// Retries server errors with exponential backoff, and honours `retry-after` or waits for
+/// an exhausted quota to reset when that happens soon enough
+fn retry_delay(
+    status: u16,
+    rate_limit: Option<RateLimit>,
+    retry_after: Option<u64>,
+    body: &str,
+    attempt: u32,
+    now: u64,
+) -> Option<Duration> {
+    match status {
+        403 | 429 => {
+            if let Some(seconds) = retry_after {
+                let wait = Duration::from_secs(seconds);
+                return (wait <= MAX_RATE_LIMIT_WAIT).then_some(wait);
+            }
+            if let Some(limit) = rate_limit.filter(|limit| limit.remaining == 0) {
+                let wait = Duration::from_secs(limit.reset.saturating_sub(now).max(1));
+                return (wait <= MAX_RATE_LIMIT_WAIT).then_some(wait);
+            }
+            if body.to_lowercase().contains("secondary rate limit") {
+                return Some(SECONDARY_LIMIT_WAIT);
+            }
+            None // A plain permission error
+        }
+        500 | 502 | 503 | 504 => Some(backoff(attempt)),
+        _ => None,
+    }
+}
+
+/// 1s, 2s, 4s, ... for transient failures
+fn backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << (attempt - 1).min(5))
+}
+
+/// Whether another attempt fits in the request's attempt and wait budgets
+fn may_retry(attempt: u32, waited: Duration, delay: Duration) -> bool {
+    attempt < MAX_ATTEMPTS && waited + delay <= MAX_REQUEST_WAIT
+}
+
+/// Connection failures and timeouts; anything else fails the same way on a retry
+fn is_transient(error: &octocrab::Error) -> bool {
+    matches!(
+        error,
+        octocrab::Error::Hyper { .. } | octocrab::Error::Service { .. }
+    )
+}
+
+/// Extracts GitHub's `message` from an error body, falling back to the raw body
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| json.get("message")?.as_str().map(|s| s.to_owned()))
+        .unwrap_or_else(|| body.to_owned())
+}
+
+/// Percent-encodes a query parameter value (branch names may contain `/`, `#`, `+`, ...)
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Maps a GraphQL `PullRequestState` to the PR status shown in the UI
+fn graphql_pr_status(state: &str) -> PrStatus {
+    match state {
+        "MERGED" => PrStatus::MERGED,
+        "OPEN" => PrStatus::OPEN,
+        "CLOSED" => PrStatus::CLOSED,
+        _ => PrStatus::NONE,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pr_cache::DEFAULT_TTL;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
     async fn can_create_offline_client() {
@@ -126,4 +886,516 @@ mod tests {
         assert_eq!(client.owner, "owner");
         assert_eq!(client.repo, "repo");
     }
+
+    #[test]
+    fn batch_query_aliases_each_branch() {
+        let query = batch_query(2);
+
+        assert!(
+            query.starts_with("query($owner: String!, $repo: String!, $h0: String!, $h1: String!)")
+        );
+        assert!(query.contains("b0: pullRequests(headRefName: $h0"));
+        assert!(query.contains("b1: pullRequests(headRefName: $h1"));
+        assert!(!query.contains("$h2"));
+    }
+
+    fn summary(number: u32, status: PrStatus, closed_at: Option<&str>) -> PrSummary {
+        PrSummary {
+            number,
+            title: format!("PR {}", number),
+            status,
+            closed_at: closed_at.map(|at| at.to_owned()),
+            head_sha: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_response_keeps_prs_from_the_head_repository() {
+        let client = GitHubClient::offline("upstream".to_string(), "repo".to_string())
+            .with_head_repo("me".to_string(), "repo".to_string());
+        let response: BatchResponse = serde_json::from_value(serde_json::json!({
+            "repository": {
+                "b0": { "totalCount": 3, "nodes": [
+                    { "number": 9, "title": "From another fork", "state": "OPEN",
+                      "closedAt": null, "headRepositoryOwner": { "login": "someone-else" } },
+                    { "number": 6, "title": "PR 6", "state": "MERGED",
+                      "closedAt": "2024-05-02T00:00:00Z", "headRepositoryOwner": { "login": "Me" } },
+                    { "number": 4, "title": "PR 4", "state": "CLOSED",
+                      "closedAt": "2024-05-01T00:00:00Z", "headRepositoryOwner": { "login": "me" } }
+                ] },
+                "b1": { "totalCount": 0, "nodes": [] }
+            }
+        }))
+        .unwrap();
+        let mut connections = response.repository.unwrap();
+
+        assert_eq!(
+            client.pick_prs(connections.remove("b0").unwrap()),
+            vec![
+                summary(6, PrStatus::MERGED, Some("2024-05-02T00:00:00Z")),
+                summary(4, PrStatus::CLOSED, Some("2024-05-01T00:00:00Z")),
+            ]
+        );
+        assert_eq!(client.pick_prs(connections.remove("b1").unwrap()), vec![]);
+    }
+
+    #[test]
+    fn open_prs_win_otherwise_the_last_closed_pr_decides() {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let branch = || BCBranch::new("feature", PrStatus::LOADING);
+        let closed = summary(3, PrStatus::CLOSED, Some("2024-01-01T00:00:00Z"));
+        let merged = summary(5, PrStatus::MERGED, Some("2024-02-01T00:00:00Z"));
+        let reclosed = summary(2, PrStatus::CLOSED, Some("2024-03-01T00:00:00Z"));
+        let open = summary(7, PrStatus::OPEN, None);
+
+        let merged_last = send_with_pr(branch(), Ok(vec![merged.clone(), closed.clone()]), &tx);
+        let closed_last = send_with_pr(branch(), Ok(vec![merged.clone(), reclosed]), &tx);
+        let open_too = send_with_pr(branch(), Ok(vec![open, merged, closed]), &tx);
+        let none = send_with_pr(branch(), Ok(vec![]), &tx);
+
+        assert_eq!(merged_last.pr_status, PrStatus::MERGED);
+        assert_eq!(merged_last.pr_number, Some(5));
+        assert_eq!(merged_last.prs.len(), 2);
+        assert_eq!(closed_last.pr_status, PrStatus::CLOSED);
+        assert_eq!(closed_last.pr_number, Some(2));
+        assert_eq!(open_too.pr_status, PrStatus::OPEN);
+        assert_eq!(open_too.pr_number, Some(7));
+        assert_eq!(none.pr_status, PrStatus::NONE);
+        assert_eq!(none.pr_number, None);
+    }
+
+    fn quota(remaining: u64, reset: u64) -> Option<RateLimit> {
+        Some(RateLimit {
+            limit: 5000,
+            remaining,
+            reset,
+        })
+    }
+
+    #[test]
+    fn reads_rate_limit_headers() {
+        let mut headers = http::HeaderMap::new();
+        headers.insert("x-ratelimit-limit", "5000".parse().unwrap());
+        headers.insert("x-ratelimit-remaining", "4321".parse().unwrap());
+        headers.insert("x-ratelimit-reset", "1700000000".parse().unwrap());
+
+        assert_eq!(
+            rate_limit_from_headers(&headers),
+            quota(4321, 1_700_000_000)
+        );
+        assert_eq!(rate_limit_from_headers(&http::HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn retries_server_errors_with_backoff() {
+        assert_eq!(
+            retry_delay(502, None, None, "", 1, 0),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            retry_delay(503, None, None, "", 3, 0),
+            Some(Duration::from_secs(4))
+        );
+        assert_eq!(retry_delay(404, None, None, "", 1, 0), None);
+        assert_eq!(retry_delay(422, None, None, "", 1, 0), None);
+    }
+
+    #[test]
+    fn waits_for_rate_limits_only_when_they_reset_soon() {
+        // retry-after wins
+        assert_eq!(
+            retry_delay(429, quota(0, 5000), Some(7), "", 1, 1000),
+            Some(Duration::from_secs(7))
+        );
+        // ... unless it asks for a longer wait than exhausted quotas get
+        assert_eq!(
+            retry_delay(429, quota(10, 5000), Some(3600), "", 1, 1000),
+            None
+        );
+        // Exhausted quota resetting in 30s: wait for it
+        assert_eq!(
+            retry_delay(403, quota(0, 1030), None, "", 1, 1000),
+            Some(Duration::from_secs(30))
+        );
+        // Resetting in an hour: give up so the branch is reported as an error
+        assert_eq!(retry_delay(403, quota(0, 4600), None, "", 1, 1000), None);
+        // Secondary limit without retry-after
+        assert_eq!(
+            retry_delay(
+                403,
+                quota(10, 4600),
+                None,
+                r#"{"message":"You have exceeded a secondary rate limit"}"#,
+                1,
+                1000
+            ),
+            Some(SECONDARY_LIMIT_WAIT)
+        );
+        // A plain permission error is not retried
+        assert_eq!(
+            retry_delay(403, quota(10, 4600), None, "Forbidden", 1, 1000),
+            None
+        );
+    }
+
+    #[test]
+    fn retries_stop_when_the_wait_budget_is_spent() {
+        assert!(may_retry(1, Duration::ZERO, SECONDARY_LIMIT_WAIT));
+        // A second secondary-limit wait would stall the lookup for two minutes
+        assert!(!may_retry(2, SECONDARY_LIMIT_WAIT, SECONDARY_LIMIT_WAIT));
+        assert!(may_retry(3, Duration::from_secs(3), backoff(3)));
+        assert!(!may_retry(MAX_ATTEMPTS, Duration::ZERO, backoff(1)));
+    }
+
+    #[test]
+    fn encodes_branch_names_in_queries() {
+        assert_eq!(
+            percent_encode("owner:feature/a+b#1"),
+            "owner%3Afeature%2Fa%2Bb%231"
+        );
+    }
+
+    #[test]
+    fn failed_lookups_are_errors_not_missing_prs() {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let branch = BCBranch::new("feature", PrStatus::LOADING);
+
+        let branch = send_with_pr(branch, Err(BranchCleanerError::RateLimited(60)), &tx);
+
+        assert_eq!(
+            branch.pr_status,
+            PrStatus::ERROR("GitHub rate limit exceeded (resets in 60s)".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_enrichment_keeps_input_order() {
+        // Arrange: More branches than fit in one batch
+        let client =
+            GitHubClient::offline("owner".to_string(), "repo".to_string()).with_concurrency(3);
+        let branches: Vec<BCBranch> = (0..120)
+            .map(|i| BCBranch::new(&format!("branch-{:03}", i), PrStatus::LOADING))
+            .collect();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        // Act
+        let enriched = client.enrich_branches_streaming(branches.clone(), tx).await;
+
+        // Assert: Every branch streamed once; the returned Vec is in input order
+        let mut streamed = 0;
+        while rx.recv().await.is_some() {
+            streamed += 1;
+        }
+        assert_eq!(streamed, 120);
+        let names: Vec<&str> = enriched.iter().map(|b| b.name.as_str()).collect();
+        let expected: Vec<&str> = branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(names, expected);
+        assert!(enriched.iter().all(|b| b.pr_status == PrStatus::UNKNOWN));
+    }
+
+    #[tokio::test]
+    async fn offline_client_finds_no_prs() {
+        let client = GitHubClient::offline("owner".to_string(), "repo".to_string());
+        assert!(client
+            .get_prs_for_branch("feature")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(client.default_branch().await.unwrap().is_none());
+        assert!(client.list_branches().await.unwrap().is_none());
+        assert_eq!(
+            client
+                .get_prs_for_branches(&["a".to_owned(), "b".to_owned()])
+                .await
+                .unwrap(),
+            vec![Vec::<PrSummary>::new(), Vec::new()]
+        );
+        assert!(matches!(
+            client.delete_branch("feature").await,
+            Err(BranchCleanerError::TokenNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn cached_results_are_served_without_lookups() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = PrCache::in_git_dir(dir.path(), "owner", "repo", DEFAULT_TTL);
+        let tip = Some("abc123".to_owned());
+        cache.record(
+            &BCBranch::with_pr("merged", PrStatus::MERGED, 4, "Done").with_tip_sha(tip.clone()),
+            None,
+            0,
+        );
+        let branches = vec![
+            BCBranch::new("merged", PrStatus::LOADING).with_tip_sha(tip.clone()),
+            BCBranch::new("new", PrStatus::LOADING).with_tip_sha(tip.clone()),
+        ];
+        let client = GitHubClient::offline("owner".to_string(), "repo".to_string());
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let enriched = client
+            .enrich_branches_cached(branches, &mut cache, tx)
+            .await;
+
+        // The merged PR comes from the cache; the offline lookup of the new branch is not cached
+        assert_eq!(
+            enriched,
+            vec![
+                BCBranch::with_pr("merged", PrStatus::MERGED, 4, "Done").with_tip_sha(tip.clone()),
+                BCBranch::new("new", PrStatus::UNKNOWN).with_tip_sha(tip.clone()),
+            ]
+        );
+        let new = BCBranch::new("new", PrStatus::LOADING).with_tip_sha(tip);
+        assert_eq!(cache.lookup(&new, 0), CacheLookup::Miss);
+    }
+
+    #[tokio::test]
+    async fn expired_cache_entries_are_unknown_offline() {
+        use crate::store::{DeletionOutcome, InMemoryBranchStore};
+        use crate::view_model::BranchViewModel;
+
+        // Arrange: a closed PR cached long ago
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = PrCache::in_git_dir(dir.path(), "owner", "repo", DEFAULT_TTL);
+        let tip = Some("abc123".to_owned());
+        let closed =
+            BCBranch::with_pr("closed", PrStatus::CLOSED, 4, "Abandoned").with_tip_sha(tip.clone());
+        cache.record(&closed, None, 0);
+        let branches = vec![BCBranch::new("closed", PrStatus::LOADING).with_tip_sha(tip)];
+        let client = GitHubClient::offline("owner".to_string(), "repo".to_string());
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+
+        // Act
+        let enriched = client
+            .enrich_branches_cached(branches, &mut cache, tx)
+            .await;
+
+        // Assert: the PR is still shown, but neither selected nor deleted on the remote
+        assert_eq!(enriched[0].pr_status, PrStatus::UNKNOWN);
+        assert_eq!(enriched[0].pr_number, Some(4));
+        let mut vm = BranchViewModel::new(InMemoryBranchStore::new(enriched))
+            .with_policy(crate::policy::parse("closed or merged").unwrap())
+            .with_delete_remote(true);
+        let mut state = vm.load_initial_state();
+        assert!(state.selected_branches.is_empty());
+        state.selected_branches = vec!["closed".to_string()];
+        let report = vm.delete_selected_branches(&mut state).await;
+        assert!(matches!(
+            report[0].remote_outcome,
+            Some(DeletionOutcome::Failed(
+                BranchCleanerError::RemoteDeleteRefused(_)
+            ))
+        ));
+    }
+
+    #[test]
+    fn enterprise_hosts_serve_the_api_under_api() {
+        let github = GitHubHost::new("github.com");
+        let enterprise = GitHubHost::new("github.example.com");
+        let custom = GitHubHost::new("github.example.com").with_config(&HostConfig {
+            api_url: Some("https://proxy.example.com/github/".to_owned()),
+            token: Some("configured".to_owned()),
+        });
+
+        assert_eq!(github.api_url, "https://api.github.com");
+        assert_eq!(github.graphql_base(), "https://api.github.com");
+        assert_eq!(enterprise.api_url, "https://github.example.com/api/v3");
+        assert_eq!(enterprise.graphql_base(), "https://github.example.com/api");
+        assert_eq!(custom.api_url, "https://proxy.example.com/github");
+        assert_eq!(custom.token.as_deref(), Some("configured"));
+    }
+
+    /// A client for an Enterprise host whose API is served by the mock server
+    async fn enterprise_client() -> (MockServer, GitHubClient) {
+        let server = MockServer::start().await;
+        let host = GitHubHost::new("github.example.com").with_config(&HostConfig {
+            api_url: Some(format!("{}/api/v3", server.uri())),
+            token: None,
+        });
+        let client = GitHubClient::new(
+            &host,
+            "owner".to_string(),
+            "repo".to_string(),
+            Token {
+                value: "secret".to_string(),
+                source: TokenSource::Config,
+            },
+        )
+        .unwrap();
+        (server, client)
+    }
+
+    #[tokio::test]
+    async fn enterprise_graphql_lookups_reach_api_graphql() {
+        // Arrange
+        let (server, client) = enterprise_client().await;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("x-ratelimit-limit", "5000")
+                    .insert_header("x-ratelimit-remaining", "4999")
+                    .insert_header("x-ratelimit-reset", "1700000000")
+                    .set_body_json(serde_json::json!({
+                        "data": { "repository": { "b0": { "totalCount": 1, "nodes": [{
+                            "number": 12,
+                            "title": "Ship it",
+                            "state": "MERGED",
+                            "closedAt": "2024-05-01T00:00:00Z",
+                            "headRepositoryOwner": { "login": "owner" }
+                        }] } } }
+                    })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Act
+        let prs = client
+            .get_prs_for_branches(&["feature".to_owned()])
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            prs,
+            vec![vec![PrSummary {
+                number: 12,
+                title: "Ship it".to_owned(),
+                status: PrStatus::MERGED,
+                closed_at: Some("2024-05-01T00:00:00Z".to_owned()),
+                head_sha: None,
+            }]]
+        );
+        assert_eq!(client.rate_limit(), quota(4999, 1_700_000_000));
+    }
+
+    #[tokio::test]
+    async fn graphql_errors_are_not_reported_as_http_failures() {
+        let (server, client) = enterprise_client().await;
+        Mock::given(method("POST"))
+            .and(path("/api/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": null,
+                "errors": [{ "type": "NOT_FOUND", "message": "Could not resolve to a Repository" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let result = client.get_prs_for_branches(&["feature".to_owned()]).await;
+
+        assert!(matches!(
+            result,
+            Err(BranchCleanerError::GraphQl(message)) if message == "Could not resolve to a Repository"
+        ));
+    }
+
+    #[tokio::test]
+    async fn enterprise_rest_lookups_are_revalidated_with_etags() {
+        // Arrange: 304 for the cached ETag, the full page otherwise
+        let (server, client) = enterprise_client().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/owner/repo/pulls"))
+            .and(query_param("head", "owner:feature"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/owner/repo/pulls"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("etag", "\"v2\"")
+                    .set_body_json(serde_json::json!([{
+                        "url": "https://github.example.com/api/v3/repos/owner/repo/pulls/12",
+                        "id": 1,
+                        "number": 12,
+                        "title": "Ship it",
+                        "state": "open",
+                        "head": { "ref": "feature", "sha": "abc" },
+                        "base": { "ref": "main", "sha": "def" }
+                    }])),
+            )
+            .mount(&server)
+            .await;
+
+        // Act
+        let unchanged = client
+            .revalidate_prs("feature", Some("\"v1\""))
+            .await
+            .unwrap();
+        let changed = client.revalidate_prs("feature", None).await.unwrap();
+
+        // Assert
+        assert_eq!(unchanged, Revalidated::Unchanged);
+        assert_eq!(
+            changed,
+            Revalidated::Changed {
+                prs: vec![PrSummary {
+                    number: 12,
+                    title: "Ship it".to_owned(),
+                    status: PrStatus::OPEN,
+                    closed_at: None,
+                    head_sha: Some("abc".to_owned()),
+                }],
+                etag: Some("\"v2\"".to_owned()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn failed_requests_report_githubs_message() {
+        let (server, client) = enterprise_client().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/owner/repo/pulls"))
+            .respond_with(
+                ResponseTemplate::new(404)
+                    .set_body_json(serde_json::json!({"message": "Not Found"})),
+            )
+            .mount(&server)
+            .await;
+
+        let result = client.get_prs_for_branch("feature").await;
+
+        assert!(matches!(
+            result,
+            Err(BranchCleanerError::GitHubStatus(404, message)) if message == "Not Found"
+        ));
+    }
+
+    #[tokio::test]
+    async fn fork_branches_are_looked_up_upstream_with_the_fork_owner() {
+        // Arrange: Branches live in me/repo, PRs are opened against owner/repo
+        let (server, client) = enterprise_client().await;
+        let client = client.with_head_repo("me".to_string(), "repo".to_string());
+        Mock::given(method("GET"))
+            .and(path("/api/v3/repos/owner/repo/pulls"))
+            .and(query_param("head", "me:feature"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                    "url": "https://github.example.com/api/v3/repos/owner/repo/pulls/3",
+                    "id": 3,
+                    "number": 3,
+                    "title": "From my fork",
+                    "state": "closed",
+                    "merged_at": "2024-05-01T00:00:00Z",
+                    "closed_at": "2024-05-01T00:00:00Z",
+                    "head": { "ref": "feature", "sha": "abc" },
+                    "base": { "ref": "main", "sha": "def" }
+                }])),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        // Act
+        let prs = client.get_prs_for_branch("feature").await.unwrap();
+
+        // Assert
+        assert_eq!(prs.len(), 1);
+        assert_eq!(prs[0].number, 3);
+        assert_eq!(prs[0].status, PrStatus::MERGED);
+    }
 }
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::unix_now;
+
+/// Number of deletion batches kept before the oldest are dropped
+const MAX_BATCHES: usize = 50;
+
+/// Everything needed to recreate a deleted local branch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub name: String,
+    /// Commit the branch pointed to when it was deleted
+    pub tip: String,
+    /// `branch.<name>.remote` config value
+    pub upstream_remote: Option<String>,
+    /// `branch.<name>.merge` config value
+    pub upstream_merge: Option<String>,
+}
+
+/// Branches deleted together in a single operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalBatch {
+    /// Seconds since the Unix epoch
+    pub deleted_at: u64,
+    pub branches: Vec<JournalEntry>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JournalFile {
+    batches: Vec<JournalBatch>,
+}
+
+/// On-disk record of deleted branches, stored inside the git directory
+/// so undo keeps working across sessions
+#[derive(Debug, Clone)]
+pub struct DeletionJournal {
+    path: PathBuf,
+}
+
+impl DeletionJournal {
+    /// Creates a journal stored under the given git directory (e.g. `.git/`)
+    pub fn in_git_dir(git_dir: impl AsRef<Path>) -> Self {
+        Self {
+            path: git_dir.as_ref().join("branch-cleaner").join("journal.json"),
+        }
+    }
+
+    /// Creates the journal of branches deleted on GitHub, stored next to the local one
+    pub fn remote_in_git_dir(git_dir: impl AsRef<Path>) -> Self {
+        Self {
+            path: git_dir
+                .as_ref()
+                .join("branch-cleaner")
+                .join("remote-journal.json"),
+        }
+    }
+
+    /// Appends a batch of deleted branches
+    pub fn record(&self, branches: Vec<JournalEntry>) -> Result<()> {
+        if branches.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = self.read()?;
+        file.batches.push(JournalBatch {
+            deleted_at: unix_now(),
+            branches,
+        });
+
+        // Keep the journal bounded: drop the oldest batches
+        let excess = file.batches.len().saturating_sub(MAX_BATCHES);
+        file.batches.drain(..excess);
+
+        self.write(&file)
+    }
+
+    /// Returns the most recent batch without removing it
+    pub fn last_batch(&self) -> Result<Option<JournalBatch>> {
+        Ok(self.read()?.batches.pop())
+    }
+
+    /// Replaces the branches of the most recent batch, removing it when none are left
+    /// (call with the branches that could not be restored)
+    pub fn retain_in_last_batch(&self, branches: Vec<JournalEntry>) -> Result<()> {
+        let mut file = self.read()?;
+        match file.batches.last_mut() {
+            Some(batch) if !branches.is_empty() => batch.branches = branches,
+            Some(_) => {
+                file.batches.pop();
+            }
+            None => return Ok(()),
+        }
+        self.write(&file)
+    }
+
+    /// Removes the most recent batch (call once it has been restored)
+    pub fn pop_last_batch(&self) -> Result<Option<JournalBatch>> {
+        let mut file = self.read()?;
+        let batch = file.batches.pop();
+        self.write(&file)?;
+        Ok(batch)
+    }
+
+    fn read(&self) -> Result<JournalFile> {
+        if !self.path.exists() {
+            return Ok(JournalFile::default());
+        }
+        let contents = fs::read_to_string(&self.path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    fn write(&self, file: &JournalFile) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(file)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str) -> JournalEntry {
+        JournalEntry {
+            name: name.to_owned(),
+            tip: "0123456789abcdef0123456789abcdef01234567".to_owned(),
+            upstream_remote: Some("origin".to_owned()),
+            upstream_merge: Some(format!("refs/heads/{}", name)),
+        }
+    }
+
+    #[test]
+    fn empty_journal_has_no_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = DeletionJournal::in_git_dir(dir.path());
+
+        assert_eq!(journal.last_batch().unwrap(), None);
+    }
+
+    #[test]
+    fn batches_are_restored_most_recent_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = DeletionJournal::in_git_dir(dir.path());
+
+        journal.record(vec![entry("first")]).unwrap();
+        journal
+            .record(vec![entry("second"), entry("third")])
+            .unwrap();
+
+        // A fresh handle reads the same file (undo across sessions)
+        let reopened = DeletionJournal::in_git_dir(dir.path());
+        let batch = reopened.pop_last_batch().unwrap().unwrap();
+        assert_eq!(batch.branches, vec![entry("second"), entry("third")]);
+
+        let batch = reopened.pop_last_batch().unwrap().unwrap();
+        assert_eq!(batch.branches, vec![entry("first")]);
+        assert_eq!(reopened.pop_last_batch().unwrap(), None);
+    }
+
+    #[test]
+    fn partly_restored_batches_keep_the_remaining_branches() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = DeletionJournal::in_git_dir(dir.path());
+        journal.record(vec![entry("first")]).unwrap();
+        journal
+            .record(vec![entry("second"), entry("third")])
+            .unwrap();
+
+        journal.retain_in_last_batch(vec![entry("third")]).unwrap();
+        assert_eq!(
+            journal.last_batch().unwrap().unwrap().branches,
+            vec![entry("third")]
+        );
+
+        journal.retain_in_last_batch(vec![]).unwrap();
+        assert_eq!(
+            journal.last_batch().unwrap().unwrap().branches,
+            vec![entry("first")]
+        );
+    }
+
+    #[test]
+    fn recording_nothing_creates_no_batch() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = DeletionJournal::in_git_dir(dir.path());
+
+        journal.record(vec![]).unwrap();
+
+        assert_eq!(journal.last_batch().unwrap(), None);
+    }
+}
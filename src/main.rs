@@ -1,76 +1,192 @@
 use core::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::Parser;
+use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "github-api")]
+mod auth;
+mod cli;
+mod config;
 mod error;
 #[cfg(feature = "github-api")]
 mod git;
 #[cfg(feature = "github-api")]
 mod github;
+#[cfg(feature = "github-api")]
+mod journal;
+mod policy;
+#[cfg(feature = "github-api")]
+mod pr_cache;
 mod store;
 mod tui;
 mod view_model;
 
 #[cfg(feature = "in-memory")]
-use store::{BranchStore, InMemoryBranchStore};
+use store::InMemoryBranchStore;
 
 #[cfg(feature = "github-api")]
 use store::GitHubBranchStore;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = cli::Cli::parse();
+    let mut config = config::Config::load(".")?;
+    cli.apply_to(&mut config);
+
     #[cfg(feature = "github-api")]
     {
         // Use real GitHub API
-        let store = GitHubBranchStore::new(".")?;
+        let store = GitHubBranchStore::new(".", &config)
+            .await?
+            .with_scope(cli.scope());
         // load() populates cache with LOADING branches and spawns async enrichment task
-        let (_initial_branches, update_rx) = store.load()?;
-        // Use slow animation for better readability
-        let animation_config = tui::AnimationConfig::slow();
-        tui::run_branch_tui(store, update_rx, animation_config)?;
+        let (_initial_branches, update_rx) = store.load().await?;
+        cli::run(cli, &config, store, update_rx).await?;
     }
 
     #[cfg(feature = "in-memory")]
     {
         // Use in-memory store for testing (no async loading needed)
         let store = InMemoryBranchStore::default();
-        // Create a dummy channel that never sends (in-memory has no async loading)
-        let (_tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let animation_config = tui::AnimationConfig::slow();
-        tui::run_branch_tui(store, rx, animation_config)?;
+        // Create a channel that closes immediately (in-memory has no async loading)
+        let (_, rx) = tokio::sync::mpsc::unbounded_channel();
+        cli::run(cli, &config, store, rx).await?;
     }
 
     Ok(())
 }
 
+/// Seconds since the Unix epoch (0 if the clock is set before it)
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 // Branch information structures
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[allow(non_camel_case_types)]
 pub enum PrStatus {
     OPEN,
     MERGED,
     CLOSED,
     NONE,
     LOADING,
+    /// No merged PR, but git history shows the work is already in the base branch
+    MERGED_LOCALLY,
+    /// The PR was merged, but the branch has this many commits the merged PR did not include
+    MERGED_WITH_CHANGES(usize),
+    /// The PR lookup failed (rate limit, network, ...) for the given reason: the PR status is unknown
+    ERROR(String),
+    /// No lookup was made (no GitHub token): the branch may well have an open PR
+    UNKNOWN,
 }
 
-impl PrStatus {
-    pub fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for PrStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
             PrStatus::OPEN => "open",
             PrStatus::MERGED => "merged",
             PrStatus::CLOSED => "closed",
             PrStatus::NONE => "No PR",
             PrStatus::LOADING => "Loading",
+            PrStatus::MERGED_LOCALLY => "merged locally",
+            PrStatus::UNKNOWN => "unknown (offline)",
+            PrStatus::MERGED_WITH_CHANGES(count) => {
+                return write!(f, "merged, {} later commit(s) not in the PR", count)
+            }
+            PrStatus::ERROR(reason) => return write!(f, "lookup failed: {}", reason),
+        };
+        f.write_str(label)
+    }
+}
+
+/// State of a branch's configured upstream (e.g. `origin/feature`)
+#[derive(Debug, PartialEq, Clone, Serialize)]
+#[serde(tag = "state", rename_all = "lowercase")]
+pub enum UpstreamStatus {
+    /// Upstream ref exists; commits only on the local branch / only on the upstream
+    Tracking {
+        name: String,
+        ahead: usize,
+        behind: usize,
+    },
+    /// Upstream is configured but the remote branch was deleted
+    Gone { name: String },
+}
+
+impl fmt::Display for UpstreamStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpstreamStatus::Gone { name } => write!(f, "{}: gone", name),
+            UpstreamStatus::Tracking {
+                name,
+                ahead: 0,
+                behind: 0,
+            } => write!(f, "{}: up to date", name),
+            UpstreamStatus::Tracking {
+                name,
+                ahead,
+                behind,
+            } => write!(f, "{}: ahead {}, behind {}", name, ahead, behind),
         }
-        .to_owned()
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// One PR opened from a branch
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PrSummary {
+    pub number: u32,
+    pub title: String,
+    /// OPEN, MERGED or CLOSED
+    pub status: PrStatus,
+    /// When the PR was merged or closed (RFC 3339); `None` while it is open
+    pub closed_at: Option<String>,
+    /// Last commit of the PR's head branch (for a merged PR, the commit that was merged)
+    pub head_sha: Option<String>,
+}
+
+impl PrSummary {
+    /// The PR that decides a branch's status: any open PR, otherwise the one merged or closed last
+    pub fn deciding(prs: &[PrSummary]) -> Option<&PrSummary> {
+        prs.iter()
+            .find(|pr| pr.status == PrStatus::OPEN)
+            .or_else(|| {
+                prs.iter()
+                    .filter(|pr| matches!(pr.status, PrStatus::MERGED | PrStatus::CLOSED))
+                    .max_by_key(|pr| (pr.closed_at.as_deref(), pr.number))
+            })
+    }
+}
+
+/// The last commit on a branch
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub struct CommitInfo {
+    /// Commit time, in seconds since the Unix epoch
+    pub time: i64,
+    pub author_name: String,
+    pub author_email: String,
+    /// First line of the commit message
+    pub subject: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct BCBranch {
     pub name: String,
+    /// Overall status, derived from `prs` once they are known
     pub pr_status: PrStatus,
     pub pr_number: Option<u32>,
     pub pr_title: Option<String>,
+    /// Every PR opened from the branch, newest first
+    pub prs: Vec<PrSummary>,
+    pub tip_sha: Option<String>,
+    /// `None` until read from git, or when the tip commit is not in the local repository
+    pub last_commit: Option<CommitInfo>,
+    /// `None` when the branch has no upstream configured
+    pub upstream: Option<UpstreamStatus>,
 }
 
 impl BCBranch {
@@ -80,21 +196,69 @@ impl BCBranch {
             pr_status,
             pr_number: None,
             pr_title: None,
+            prs: Vec::new(),
+            tip_sha: None,
+            last_commit: None,
+            upstream: None,
         }
     }
 
     pub fn with_pr(name: &str, pr_status: PrStatus, pr_number: u32, pr_title: &str) -> Self {
         Self {
             name: name.to_owned(),
-            pr_status,
+            pr_status: pr_status.clone(),
             pr_number: Some(pr_number),
             pr_title: Some(pr_title.to_owned()),
+            prs: vec![PrSummary {
+                number: pr_number,
+                title: pr_title.to_owned(),
+                status: pr_status,
+                closed_at: None,
+                head_sha: None,
+            }],
+            tip_sha: None,
+            last_commit: None,
+            upstream: None,
         }
     }
+
+    /// Sets the branch's PRs and the status, number and title of the one that decides
+    /// (see `PrSummary::deciding`); NONE when there are none
+    pub fn with_prs(mut self, prs: Vec<PrSummary>) -> Self {
+        let deciding = PrSummary::deciding(&prs);
+        self.pr_status = deciding.map_or(PrStatus::NONE, |pr| pr.status.clone());
+        self.pr_number = deciding.map(|pr| pr.number);
+        self.pr_title = deciding.map(|pr| pr.title.clone());
+        self.prs = prs;
+        self
+    }
+
+    /// Sets the SHA of the commit the branch points to
+    pub fn with_tip_sha(mut self, tip_sha: Option<String>) -> Self {
+        self.tip_sha = tip_sha;
+        self
+    }
+
+    /// Sets the details of the branch's last commit
+    pub fn with_last_commit(mut self, last_commit: Option<CommitInfo>) -> Self {
+        self.last_commit = last_commit;
+        self
+    }
+
+    /// Sets the state of the branch's upstream
+    pub fn with_upstream(mut self, upstream: Option<UpstreamStatus>) -> Self {
+        self.upstream = upstream;
+        self
+    }
+
+    /// True when the branch tracked a remote branch that no longer exists
+    pub fn is_upstream_gone(&self) -> bool {
+        matches!(self.upstream, Some(UpstreamStatus::Gone { .. }))
+    }
 }
 
 impl fmt::Display for BCBranch {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} | {}", self.name, self.pr_status.to_string())
+        write!(f, "{} | {}", self.name, self.pr_status)
     }
 }
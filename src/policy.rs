@@ -0,0 +1,542 @@
+use std::fmt;
+
+use crate::config::Config;
+use crate::error::{BranchCleanerError, Result};
+use crate::{unix_now, BCBranch, PrStatus, PrSummary};
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// The rule that selected a branch for deletion
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectionRule {
+    Merged,
+    MergedLocally,
+    GoneUpstream,
+    /// Last commit made this many days ago
+    Stale {
+        days: u64,
+    },
+    /// PR closed without merging, this many days ago when known
+    Closed {
+        days: Option<u64>,
+    },
+    /// Every rule of an `and` policy
+    All(Vec<SelectionRule>),
+}
+
+impl fmt::Display for SelectionRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SelectionRule::Merged => f.write_str("merged"),
+            SelectionRule::MergedLocally => f.write_str("merged (git history)"),
+            SelectionRule::GoneUpstream => f.write_str("upstream gone"),
+            SelectionRule::Stale { days } => write!(f, "no commits in {} days", days),
+            SelectionRule::Closed { days: Some(days) } => {
+                write!(f, "PR closed {} days ago", days)
+            }
+            SelectionRule::Closed { days: None } => f.write_str("PR closed"),
+            SelectionRule::All(rules) => {
+                let rules: Vec<String> = rules.iter().map(ToString::to_string).collect();
+                f.write_str(&rules.join(", "))
+            }
+        }
+    }
+}
+
+/// Decides which branches are selected for deletion without user input
+/// Displays as the expression `parse` accepts
+pub trait SelectionPolicy: fmt::Debug + fmt::Display + Send + Sync {
+    /// The rule selecting the branch at `now` (seconds since the Unix epoch), if any
+    fn rule_at(&self, branch: &BCBranch, now: i64) -> Option<SelectionRule>;
+
+    /// The rule selecting the branch right now, if any
+    fn rule_for(&self, branch: &BCBranch) -> Option<SelectionRule> {
+        self.rule_at(branch, unix_now() as i64)
+    }
+
+    /// Returns true if the branch should be selected without user input
+    fn matches(&self, branch: &BCBranch) -> bool {
+        self.rule_for(branch).is_some()
+    }
+
+    /// Names of the branches this policy selects
+    fn select(&self, branches: &[BCBranch]) -> Vec<String> {
+        branches
+            .iter()
+            .filter(|b| self.matches(b))
+            .map(|b| b.name.clone())
+            .collect()
+    }
+}
+
+/// Selects branches whose PR was merged (`merged`)
+#[derive(Debug, Clone, Copy)]
+pub struct Merged;
+
+impl SelectionPolicy for Merged {
+    fn rule_at(&self, branch: &BCBranch, _now: i64) -> Option<SelectionRule> {
+        (branch.pr_status == PrStatus::MERGED).then_some(SelectionRule::Merged)
+    }
+}
+
+impl fmt::Display for Merged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("merged")
+    }
+}
+
+/// Selects branches without a merged PR whose work is in the base branch (`merged-locally`)
+#[derive(Debug, Clone, Copy)]
+pub struct MergedLocally;
+
+impl SelectionPolicy for MergedLocally {
+    fn rule_at(&self, branch: &BCBranch, _now: i64) -> Option<SelectionRule> {
+        (branch.pr_status == PrStatus::MERGED_LOCALLY).then_some(SelectionRule::MergedLocally)
+    }
+}
+
+impl fmt::Display for MergedLocally {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("merged-locally")
+    }
+}
+
+/// Selects branches whose upstream branch was deleted (`gone`, or `gone-upstream` in full)
+#[derive(Debug, Clone, Copy)]
+pub struct GoneUpstream;
+
+impl SelectionPolicy for GoneUpstream {
+    fn rule_at(&self, branch: &BCBranch, _now: i64) -> Option<SelectionRule> {
+        (!may_lose_work(branch) && branch.is_upstream_gone()).then_some(SelectionRule::GoneUpstream)
+    }
+}
+
+impl fmt::Display for GoneUpstream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("gone")
+    }
+}
+
+/// Selects branches whose PR was closed without merging, at least `min_days` ago
+/// when given (`closed`, `closed:30`)
+#[derive(Debug, Clone, Copy)]
+pub struct Closed {
+    pub min_days: Option<u64>,
+}
+
+impl SelectionPolicy for Closed {
+    fn rule_at(&self, branch: &BCBranch, now: i64) -> Option<SelectionRule> {
+        if may_lose_work(branch) {
+            return None;
+        }
+        let pr = PrSummary::deciding(&branch.prs).filter(|pr| pr.status == PrStatus::CLOSED)?;
+        let days = pr
+            .closed_at
+            .as_deref()
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+            .map(|at| days_between(at.timestamp(), now));
+        match (self.min_days, days) {
+            (None, days) => Some(SelectionRule::Closed { days }),
+            (Some(limit), Some(days)) if days >= limit => {
+                Some(SelectionRule::Closed { days: Some(days) })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Closed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.min_days {
+            Some(days) => write!(f, "closed:{}", days),
+            None => f.write_str("closed"),
+        }
+    }
+}
+
+/// Selects branches without commits for at least `days` days (`stale:90`)
+#[derive(Debug, Clone, Copy)]
+pub struct Stale {
+    pub days: u64,
+}
+
+impl SelectionPolicy for Stale {
+    fn rule_at(&self, branch: &BCBranch, now: i64) -> Option<SelectionRule> {
+        if may_lose_work(branch) {
+            return None;
+        }
+        let days = days_between(branch.last_commit.as_ref()?.time, now);
+        (days >= self.days).then_some(SelectionRule::Stale { days })
+    }
+}
+
+impl fmt::Display for Stale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stale:{}", self.days)
+    }
+}
+
+/// Selects branches matched by every policy (`a and b`)
+#[derive(Debug)]
+pub struct And(pub Vec<Box<dyn SelectionPolicy>>);
+
+impl SelectionPolicy for And {
+    fn rule_at(&self, branch: &BCBranch, now: i64) -> Option<SelectionRule> {
+        let mut rules = self
+            .0
+            .iter()
+            .map(|policy| policy.rule_at(branch, now))
+            .collect::<Option<Vec<_>>>()?;
+        match rules.len() {
+            0 => None,
+            1 => rules.pop(),
+            _ => Some(SelectionRule::All(rules)),
+        }
+    }
+}
+
+impl fmt::Display for And {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self
+            .0
+            .iter()
+            .map(|policy| match policy.to_string() {
+                or if or.contains(" or ") => format!("({})", or),
+                part => part,
+            })
+            .collect();
+        f.write_str(&parts.join(" and "))
+    }
+}
+
+/// Selects branches matched by any policy, with the rule of the first one matching (`a or b`)
+#[derive(Debug)]
+pub struct Or(pub Vec<Box<dyn SelectionPolicy>>);
+
+impl SelectionPolicy for Or {
+    fn rule_at(&self, branch: &BCBranch, now: i64) -> Option<SelectionRule> {
+        self.0.iter().find_map(|policy| policy.rule_at(branch, now))
+    }
+}
+
+impl fmt::Display for Or {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(ToString::to_string).collect();
+        f.write_str(&parts.join(" or "))
+    }
+}
+
+/// The policy used unless configured otherwise: `merged or merged-locally`
+pub fn default_policy() -> Or {
+    from_rules(true, false, None, None)
+}
+
+/// Combines the built-in rules enabled by individual options with `or`
+pub fn from_rules(
+    merged: bool,
+    gone_upstream: bool,
+    stale_days: Option<u64>,
+    closed_days: Option<u64>,
+) -> Or {
+    let mut policies: Vec<Box<dyn SelectionPolicy>> = Vec::new();
+    if merged {
+        policies.push(Box::new(Merged));
+        policies.push(Box::new(MergedLocally));
+    }
+    if gone_upstream {
+        policies.push(Box::new(GoneUpstream));
+    }
+    if let Some(days) = closed_days {
+        policies.push(Box::new(Closed {
+            min_days: Some(days),
+        }));
+    }
+    if let Some(days) = stale_days {
+        policies.push(Box::new(Stale { days }));
+    }
+    Or(policies)
+}
+
+/// The policy set by `[select] policy` (or `--policy`), otherwise the one built from
+/// the individual rule options
+pub fn configured(config: &Config) -> Result<Box<dyn SelectionPolicy>> {
+    if let Some(expr) = &config.select.policy {
+        return parse(expr);
+    }
+    Ok(Box::new(from_rules(
+        true,
+        config.defaults.select_gone.unwrap_or(false),
+        config.select.stale_days,
+        config.select.closed_days,
+    )))
+}
+
+/// Parses a policy expression such as `merged or (gone and stale:90)`
+/// `and` binds tighter than `or`
+pub fn parse(expr: &str) -> Result<Box<dyn SelectionPolicy>> {
+    let mut parser = Parser {
+        source: expr,
+        tokens: tokenize(expr),
+        pos: 0,
+    };
+    let policy = parser.or()?;
+    match parser.next() {
+        None => Ok(policy),
+        Some(token) => Err(invalid(expr, &format!("unexpected '{}'", token))),
+    }
+}
+
+fn invalid(expr: &str, reason: &str) -> BranchCleanerError {
+    BranchCleanerError::ConfigError(format!("invalid selection policy '{}': {}", expr, reason))
+}
+
+/// Splits an expression into words and parentheses
+fn tokenize(expr: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in expr.chars() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+/// Recursive descent over the tokens of one expression
+struct Parser<'a> {
+    source: &'a str,
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Box<dyn SelectionPolicy>> {
+        let mut policies = vec![self.and()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("or")) {
+            self.pos += 1;
+            policies.push(self.and()?);
+        }
+        Ok(match policies.len() {
+            1 => policies.remove(0),
+            _ => Box::new(Or(policies)),
+        })
+    }
+
+    fn and(&mut self) -> Result<Box<dyn SelectionPolicy>> {
+        let mut policies = vec![self.atom()?];
+        while self.peek().is_some_and(|t| t.eq_ignore_ascii_case("and")) {
+            self.pos += 1;
+            policies.push(self.atom()?);
+        }
+        Ok(match policies.len() {
+            1 => policies.remove(0),
+            _ => Box::new(And(policies)),
+        })
+    }
+
+    fn atom(&mut self) -> Result<Box<dyn SelectionPolicy>> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err(invalid(self.source, "expected a rule")),
+        };
+        if token == "(" {
+            let policy = self.or()?;
+            return match self.next().as_deref() {
+                Some(")") => Ok(policy),
+                _ => Err(invalid(self.source, "missing ')'")),
+            };
+        }
+
+        let (name, days) = match token.split_once(':') {
+            Some((name, days)) => match days.parse::<u64>() {
+                Ok(days) => (name, Some(days)),
+                Err(_) => {
+                    let reason = format!("'{}' is not a number of days", days);
+                    return Err(invalid(self.source, &reason));
+                }
+            },
+            None => (token.as_str(), None),
+        };
+        let policy: Box<dyn SelectionPolicy> = match (name, days) {
+            ("merged", None) => Box::new(Merged),
+            ("merged-locally", None) => Box::new(MergedLocally),
+            ("gone" | "gone-upstream", None) => Box::new(GoneUpstream),
+            ("closed", min_days) => Box::new(Closed { min_days }),
+            ("stale", Some(days)) => Box::new(Stale { days }),
+            ("stale", None) => {
+                return Err(invalid(
+                    self.source,
+                    "stale needs a number of days, e.g. stale:90",
+                ))
+            }
+            ("merged" | "merged-locally" | "gone" | "gone-upstream", Some(_)) => {
+                let reason = format!("{} takes no number of days", name);
+                return Err(invalid(self.source, &reason));
+            }
+            _ => {
+                let reason = format!(
+                    "unknown rule '{}' (expected merged, merged-locally, gone, closed or stale)",
+                    name
+                );
+                return Err(invalid(self.source, &reason));
+            }
+        };
+        Ok(policy)
+    }
+}
+
+/// Whole days from `then` to `now` (both in seconds since the Unix epoch)
+pub fn days_between(then: i64, now: i64) -> u64 {
+    (now.saturating_sub(then) / SECONDS_PER_DAY).max(0) as u64
+}
+
+/// Branches with an open (or not yet known) PR, or with commits made after their PR
+/// was merged, are never selected by the upstream, age or closed rules.
+/// A PR status that was never looked up (offline) is not known either
+fn may_lose_work(branch: &BCBranch) -> bool {
+    matches!(
+        branch.pr_status,
+        PrStatus::OPEN
+            | PrStatus::LOADING
+            | PrStatus::ERROR(_)
+            | PrStatus::UNKNOWN
+            | PrStatus::MERGED_WITH_CHANGES(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CommitInfo, UpstreamStatus};
+
+    fn committed_on_day(day: i64) -> Option<CommitInfo> {
+        Some(CommitInfo {
+            time: day * SECONDS_PER_DAY,
+            author_name: "Test".to_owned(),
+            author_email: "test@example.com".to_owned(),
+            subject: "work".to_owned(),
+        })
+    }
+
+    #[test]
+    fn parses_and_before_or() {
+        let policy = parse("merged or gone and stale:90").unwrap();
+        let grouped = parse("(merged OR gone) and closed").unwrap();
+
+        assert_eq!(policy.to_string(), "merged or gone and stale:90");
+        assert_eq!(grouped.to_string(), "(merged or gone) and closed");
+        assert_eq!(parse("gone-upstream").unwrap().to_string(), "gone");
+        assert_eq!(default_policy().to_string(), "merged or merged-locally");
+    }
+
+    #[test]
+    fn rejects_malformed_policies() {
+        let error = |expr: &str| parse(expr).unwrap_err().to_string();
+
+        assert!(error("merged or").contains("expected a rule"));
+        assert!(error("(merged or gone").contains("missing ')'"));
+        assert!(error("merged gone").contains("unexpected 'gone'"));
+        assert!(error("stale").contains("stale needs a number of days"));
+        assert!(error("stale:soon").contains("'soon' is not a number of days"));
+        assert!(error("abandoned").contains("unknown rule 'abandoned'"));
+    }
+
+    #[test]
+    fn and_requires_every_rule_and_reports_them_all() {
+        // Arrange: "now" is day 100
+        let now = 100 * SECONDS_PER_DAY;
+        let gone = Some(UpstreamStatus::Gone {
+            name: "origin/old".to_owned(),
+        });
+        let policy = parse("merged or gone and stale:30").unwrap();
+
+        let gone_and_old = BCBranch::new("old", PrStatus::NONE)
+            .with_upstream(gone.clone())
+            .with_last_commit(committed_on_day(10));
+        let gone_but_recent = BCBranch::new("recent", PrStatus::NONE)
+            .with_upstream(gone)
+            .with_last_commit(committed_on_day(95));
+        let merged = BCBranch::with_pr("done", PrStatus::MERGED, 1, "Done");
+
+        // Act + Assert
+        let rule = policy.rule_at(&gone_and_old, now);
+        assert_eq!(
+            rule,
+            Some(SelectionRule::All(vec![
+                SelectionRule::GoneUpstream,
+                SelectionRule::Stale { days: 90 },
+            ]))
+        );
+        assert_eq!(
+            rule.unwrap().to_string(),
+            "upstream gone, no commits in 90 days"
+        );
+        assert_eq!(policy.rule_at(&gone_but_recent, now), None);
+        assert_eq!(policy.rule_at(&merged, now), Some(SelectionRule::Merged));
+    }
+
+    #[test]
+    fn rules_do_not_match_branches_whose_pr_status_is_unknown() {
+        // Arrange: an old, gone branch whose closed PR was cached but not looked up this run
+        let now = 100 * SECONDS_PER_DAY;
+        let closed = PrSummary {
+            number: 7,
+            title: "Abandoned".to_owned(),
+            status: PrStatus::CLOSED,
+            closed_at: Some("1970-01-02T00:00:00Z".to_owned()),
+            head_sha: None,
+        };
+        let looked_up = BCBranch::new("old", PrStatus::NONE)
+            .with_upstream(Some(UpstreamStatus::Gone {
+                name: "origin/old".to_owned(),
+            }))
+            .with_last_commit(committed_on_day(10))
+            .with_prs(vec![closed]);
+        let offline = BCBranch {
+            pr_status: PrStatus::UNKNOWN,
+            ..looked_up.clone()
+        };
+
+        // Act + Assert
+        for expr in ["gone", "stale:30", "closed", "closed:30"] {
+            let policy = parse(expr).unwrap();
+            assert!(policy.rule_at(&looked_up, now).is_some(), "{}", expr);
+            assert_eq!(policy.rule_at(&offline, now), None, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn configured_policy_replaces_the_individual_rules() {
+        let mut config = Config::parse("select = { stale_days = 90 }").unwrap();
+        assert_eq!(
+            configured(&config).unwrap().to_string(),
+            "merged or merged-locally or stale:90"
+        );
+
+        config.select.policy = Some("merged and closed".to_owned());
+        assert_eq!(
+            configured(&config).unwrap().to_string(),
+            "merged and closed"
+        );
+    }
+}
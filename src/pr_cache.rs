@@ -0,0 +1,227 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::{BCBranch, PrStatus, PrSummary};
+
+/// How long an OPEN, CLOSED (or PR-less) result is trusted before it is revalidated
+pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// A PR lookup result remembered between runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedPr {
+    /// Branch tip the lookup was made for; a new commit invalidates the entry
+    pub head_sha: String,
+    /// Every PR found for the branch
+    pub prs: Vec<PrSummary>,
+    /// ETag of the REST response, sent back when revalidating
+    pub etag: Option<String>,
+    /// Seconds since the Unix epoch
+    pub checked_at: u64,
+}
+
+impl CachedPr {
+    /// Merged PRs never change, so their results are kept forever
+    /// Closed ones are not final: the PR can be reopened, or a new one opened from the same tip
+    fn is_final(&self) -> bool {
+        PrSummary::deciding(&self.prs).is_some_and(|pr| pr.status == PrStatus::MERGED)
+    }
+}
+
+/// What the cache knows about a branch
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheLookup {
+    /// Usable as is
+    Fresh(CachedPr),
+    /// Older than the TTL: revalidate before trusting it
+    Stale(CachedPr),
+    /// Never looked up, or the branch moved since
+    Miss,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    entries: HashMap<String, CachedPr>,
+}
+
+/// On-disk cache of PR lookups, stored inside the git directory
+/// Entries are keyed by `owner/repo/branch` and only match the branch tip they were made for
+#[derive(Debug, Clone)]
+pub struct PrCache {
+    path: PathBuf,
+    repo: String,
+    ttl: Duration,
+    entries: HashMap<String, CachedPr>,
+}
+
+impl PrCache {
+    /// Opens the cache under the given git directory (e.g. `.git/`)
+    /// A missing or unreadable cache file starts an empty cache
+    pub fn in_git_dir(git_dir: impl AsRef<Path>, owner: &str, repo: &str, ttl: Duration) -> Self {
+        let path = git_dir
+            .as_ref()
+            .join("branch-cleaner")
+            .join("pr-cache.json");
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<CacheFile>(&contents).ok())
+            .unwrap_or_default()
+            .entries;
+        Self {
+            path,
+            repo: format!("{}/{}", owner, repo),
+            ttl,
+            entries,
+        }
+    }
+
+    /// Looks up the cached result for the branch's current tip
+    pub fn lookup(&self, branch: &BCBranch, now: u64) -> CacheLookup {
+        let cached = match (&branch.tip_sha, self.entries.get(&self.key(&branch.name))) {
+            (Some(tip), Some(cached)) if &cached.head_sha == tip => cached,
+            _ => return CacheLookup::Miss,
+        };
+        if cached.is_final() || now.saturating_sub(cached.checked_at) < self.ttl.as_secs() {
+            CacheLookup::Fresh(cached.clone())
+        } else {
+            CacheLookup::Stale(cached.clone())
+        }
+    }
+
+    /// Remembers the PR found for a branch
+    /// Branches without a known tip and failed or unfinished lookups are not cached
+    pub fn record(&mut self, branch: &BCBranch, etag: Option<String>, now: u64) {
+        let tip = match &branch.tip_sha {
+            Some(tip) => tip,
+            None => return,
+        };
+        if !matches!(
+            branch.pr_status,
+            PrStatus::OPEN | PrStatus::MERGED | PrStatus::CLOSED | PrStatus::NONE
+        ) {
+            return;
+        }
+        self.entries.insert(
+            self.key(&branch.name),
+            CachedPr {
+                head_sha: tip.clone(),
+                prs: branch.prs.clone(),
+                etag,
+                checked_at: now,
+            },
+        );
+    }
+
+    /// Marks a cached result as confirmed (GitHub answered 304 Not Modified)
+    pub fn touch(&mut self, name: &str, now: u64) {
+        if let Some(cached) = self.entries.get_mut(&self.key(name)) {
+            cached.checked_at = now;
+        }
+    }
+
+    /// Writes the cache back to disk
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = CacheFile {
+            entries: self.entries.clone(),
+        };
+        fs::write(&self.path, serde_json::to_string(&file)?)?;
+        Ok(())
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}/{}", self.repo, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TIP: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn branch(name: &str, status: PrStatus) -> BCBranch {
+        BCBranch::with_pr(name, status, 7, "Title").with_tip_sha(Some(TIP.to_owned()))
+    }
+
+    #[test]
+    fn results_survive_a_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = PrCache::in_git_dir(dir.path(), "owner", "repo", DEFAULT_TTL);
+        cache.record(&branch("feature", PrStatus::MERGED), None, 100);
+        cache.save().unwrap();
+
+        let reopened = PrCache::in_git_dir(dir.path(), "owner", "repo", DEFAULT_TTL);
+
+        match reopened.lookup(&branch("feature", PrStatus::LOADING), 100) {
+            CacheLookup::Fresh(cached) => {
+                assert_eq!(cached.prs, branch("feature", PrStatus::MERGED).prs)
+            }
+            other => panic!("expected a fresh entry, got {:?}", other),
+        }
+        // Other repositories sharing the git directory do not see the entry
+        let other = PrCache::in_git_dir(dir.path(), "owner", "fork", DEFAULT_TTL);
+        assert_eq!(
+            other.lookup(&branch("feature", PrStatus::LOADING), 100),
+            CacheLookup::Miss
+        );
+    }
+
+    #[test]
+    fn open_and_closed_results_go_stale_but_merged_ones_do_not() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = PrCache::in_git_dir(dir.path(), "owner", "repo", DEFAULT_TTL);
+        cache.record(
+            &branch("open", PrStatus::OPEN),
+            Some("\"etag\"".to_owned()),
+            0,
+        );
+        cache.record(&branch("merged", PrStatus::MERGED), None, 0);
+        cache.record(&branch("closed", PrStatus::CLOSED), None, 0);
+        let later = DEFAULT_TTL.as_secs() + 1;
+
+        assert!(matches!(
+            cache.lookup(&branch("open", PrStatus::LOADING), later),
+            CacheLookup::Stale(CachedPr { etag: Some(_), .. })
+        ));
+        assert!(matches!(
+            cache.lookup(&branch("merged", PrStatus::LOADING), later),
+            CacheLookup::Fresh(_)
+        ));
+        // Closed PRs can be reopened
+        assert!(matches!(
+            cache.lookup(&branch("closed", PrStatus::LOADING), later),
+            CacheLookup::Stale(_)
+        ));
+
+        cache.touch("open", later);
+        assert!(matches!(
+            cache.lookup(&branch("open", PrStatus::LOADING), later),
+            CacheLookup::Fresh(_)
+        ));
+    }
+
+    #[test]
+    fn new_commits_and_failures_are_not_served_from_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = PrCache::in_git_dir(dir.path(), "owner", "repo", DEFAULT_TTL);
+        cache.record(&branch("moved", PrStatus::MERGED), None, 0);
+        cache.record(
+            &branch("failed", PrStatus::ERROR("timeout".to_owned())),
+            None,
+            0,
+        );
+
+        let moved = branch("moved", PrStatus::LOADING).with_tip_sha(Some("feed".to_owned()));
+        assert_eq!(cache.lookup(&moved, 0), CacheLookup::Miss);
+        assert_eq!(
+            cache.lookup(&branch("failed", PrStatus::LOADING), 0),
+            CacheLookup::Miss
+        );
+    }
+}
@@ -3,90 +3,235 @@ use std::path::Path;
 
 #[cfg(feature = "github-api")]
 use std::sync::{Arc, Mutex};
+#[cfg(feature = "github-api")]
+use std::time::Duration;
 
 #[cfg(feature = "github-api")]
 use tokio::sync::mpsc::{self, UnboundedReceiver};
 
 #[cfg(feature = "github-api")]
-use crate::error::Result;
+use crate::config::Config;
+use crate::error::{BranchCleanerError, Result};
+#[cfg(feature = "github-api")]
+use crate::git::{GitHubRemote, GitRepository, DEFAULT_REMOTE};
+#[cfg(feature = "github-api")]
+use crate::github::{GitHubClient, GitHubHost, DEFAULT_CONCURRENCY};
 #[cfg(feature = "github-api")]
-use crate::git::GitRepository;
+use crate::journal::JournalEntry;
 #[cfg(feature = "github-api")]
-use crate::github::GitHubClient;
+use crate::pr_cache::{PrCache, DEFAULT_TTL};
+#[cfg(feature = "github-api")]
+use crate::PrSummary;
+#[cfg(any(test, feature = "in-memory"))]
+use crate::UpstreamStatus;
 use crate::{BCBranch, PrStatus};
 
+/// What happened to a single branch when the store tried to delete it
+#[derive(Debug)]
+pub enum DeletionOutcome {
+    Deleted,
+    /// The branch no longer exists, so there was nothing to delete
+    Skipped,
+    Failed(BranchCleanerError),
+}
+
+/// Per-branch result of `BranchStore::delete_branches`
+#[derive(Debug)]
+pub struct DeletionResult {
+    pub name: String,
+    pub outcome: DeletionOutcome,
+}
+
+impl DeletionResult {
+    pub fn new(name: &str, outcome: DeletionOutcome) -> Self {
+        Self {
+            name: name.to_owned(),
+            outcome,
+        }
+    }
+}
+
+/// GitHub API quota, as reported by the `x-ratelimit-*` headers of the latest response
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    /// Seconds since the Unix epoch when the quota resets
+    pub reset: u64,
+}
+
+/// Which branches a store manages
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BranchScope {
+    /// Local branches (`refs/heads/*`)
+    #[default]
+    Local,
+    /// Branches on the remote (`origin` unless `--remote` says otherwise), deleted through the GitHub API
+    Remote,
+}
+
 /// BranchStore trait for managing BCBranch objects
 /// This is a higher-level abstraction that works with
 /// rich domain objects (BCBranch) instead of just branch names
+/// Operations that may wait on GitHub are async
 pub trait BranchStore: std::fmt::Debug + Clone + Send + Sync + 'static {
     /// Returns all branches from the store
     fn list_branches(&self) -> Vec<BCBranch>;
 
     /// Deletes branches by name from the store
-    fn delete_branches(&mut self, names: &[String]);
+    /// Returns one result per requested name, in the same order
+    async fn delete_branches(&mut self, names: &[String]) -> Vec<DeletionResult>;
+
+    /// Deletes the branches of the same name on the remote; local branches are left alone
+    /// Returns one result per requested name, in the same order
+    fn delete_remote_branches(&mut self, names: &[String]) -> Vec<DeletionResult>;
+
+    /// Restores the most recently deleted batch of branches
+    /// Returns the names of the branches that were brought back, or an error naming those
+    /// that could not be (the ones that were stay restored)
+    async fn restore_deleted_branches(&mut self) -> Result<Vec<String>>;
+
+    /// The branch other branches are merged into (the repository's default branch)
+    fn base_branch(&self) -> Option<String> {
+        None
+    }
+
+    /// Which branches the store lists and deletes
+    fn scope(&self) -> BranchScope {
+        BranchScope::Local
+    }
+
+    /// Remaining GitHub API quota, once a request has reported it
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+
+    /// Where the GitHub token came from, for display (`None` when the store does not use GitHub)
+    fn token_source(&self) -> Option<String> {
+        None
+    }
+
+    /// Looks up the PR of one branch again (e.g. after the lookup failed)
+    /// Returns the refreshed branch, or `None` if the store does not list it
+    async fn retry_pr_lookup(&mut self, name: &str) -> Option<BCBranch> {
+        self.list_branches().into_iter().find(|b| b.name == name)
+    }
 }
 
 /// In-memory implementation of BranchStore for testing and demo purposes
+#[cfg(any(test, feature = "in-memory"))]
 #[derive(Debug, Clone)]
 pub struct InMemoryBranchStore {
     branches: Vec<BCBranch>,
+    // Deleted batches, most recent last (for undo)
+    deleted: Vec<Vec<BCBranch>>,
 }
 
+#[cfg(any(test, feature = "in-memory"))]
 impl InMemoryBranchStore {
     /// Creates a new InMemoryBranchStore with the given branches
     pub fn new(branches: Vec<BCBranch>) -> Self {
-        Self { branches }
+        Self {
+            branches,
+            deleted: Vec::new(),
+        }
     }
 }
 
+#[cfg(any(test, feature = "in-memory"))]
 impl Default for InMemoryBranchStore {
     fn default() -> Self {
-        Self {
-            branches: vec![
-                BCBranch::new("main", PrStatus::NONE),
-                BCBranch::with_pr(
-                    "feature/add-tui",
-                    PrStatus::OPEN,
-                    42,
-                    "Add TUI interface with Ratatui",
-                ),
-                BCBranch::with_pr(
-                    "old-feature-branch",
-                    PrStatus::MERGED,
-                    23,
-                    "Old feature implementation",
-                ),
-                BCBranch::new("experimental/refactor", PrStatus::NONE),
-                BCBranch::with_pr(
-                    "bugfix/handle-errors",
-                    PrStatus::MERGED,
-                    15,
-                    "Fix error handling in repository",
-                ),
-                BCBranch::with_pr(
-                    "feature/github-integration",
-                    PrStatus::OPEN,
-                    50,
-                    "Integrate GitHub API for PR fetching",
-                ),
-                BCBranch::with_pr(
-                    "cleanup/remove-old-code",
-                    PrStatus::MERGED,
-                    31,
-                    "Remove deprecated functions and cleanup",
-                ),
-            ],
-        }
+        Self::new(vec![
+            BCBranch::new("main", PrStatus::NONE),
+            BCBranch::with_pr(
+                "feature/add-tui",
+                PrStatus::OPEN,
+                42,
+                "Add TUI interface with Ratatui",
+            ),
+            BCBranch::with_pr(
+                "old-feature-branch",
+                PrStatus::MERGED,
+                23,
+                "Old feature implementation",
+            ),
+            BCBranch::new("experimental/refactor", PrStatus::NONE),
+            BCBranch::with_pr(
+                "bugfix/handle-errors",
+                PrStatus::MERGED,
+                15,
+                "Fix error handling in repository",
+            ),
+            BCBranch::with_pr(
+                "feature/github-integration",
+                PrStatus::OPEN,
+                50,
+                "Integrate GitHub API for PR fetching",
+            ),
+            BCBranch::with_pr(
+                "cleanup/remove-old-code",
+                PrStatus::MERGED,
+                31,
+                "Remove deprecated functions and cleanup",
+            ),
+        ])
     }
 }
 
+#[cfg(any(test, feature = "in-memory"))]
 impl BranchStore for InMemoryBranchStore {
     fn list_branches(&self) -> Vec<BCBranch> {
         self.branches.clone()
     }
 
-    fn delete_branches(&mut self, names: &[String]) {
-        self.branches.retain(|b| !names.contains(&b.name));
+    async fn delete_branches(&mut self, names: &[String]) -> Vec<DeletionResult> {
+        let (deleted, kept): (Vec<BCBranch>, Vec<BCBranch>) = self
+            .branches
+            .drain(..)
+            .partition(|b| names.contains(&b.name));
+        self.branches = kept;
+
+        let results = names
+            .iter()
+            .map(|name| {
+                let outcome = if deleted.iter().any(|b| &b.name == name) {
+                    DeletionOutcome::Deleted
+                } else {
+                    DeletionOutcome::Skipped
+                };
+                DeletionResult::new(name, outcome)
+            })
+            .collect();
+        self.deleted.push(deleted);
+        results
+    }
+
+    fn delete_remote_branches(&mut self, names: &[String]) -> Vec<DeletionResult> {
+        // A branch tracking an existing upstream stands in for one that exists on origin
+        names
+            .iter()
+            .map(|name| {
+                let branch = self.branches.iter_mut().find(|b| &b.name == name);
+                let outcome = match branch {
+                    Some(branch) => match &branch.upstream {
+                        Some(UpstreamStatus::Tracking { name, .. }) => {
+                            branch.upstream = Some(UpstreamStatus::Gone { name: name.clone() });
+                            DeletionOutcome::Deleted
+                        }
+                        _ => DeletionOutcome::Skipped,
+                    },
+                    None => DeletionOutcome::Skipped,
+                };
+                DeletionResult::new(name, outcome)
+            })
+            .collect()
+    }
+
+    async fn restore_deleted_branches(&mut self) -> Result<Vec<String>> {
+        let restored = self.deleted.pop().unwrap_or_default();
+        let names = restored.iter().map(|b| b.name.clone()).collect();
+        self.branches.extend(restored);
+        Ok(names)
     }
 }
 
@@ -96,48 +241,123 @@ impl BranchStore for InMemoryBranchStore {
 pub struct GitHubBranchStore {
     git: GitRepository,
     github: GitHubClient,
+    scope: BranchScope,
     // Cache to avoid repeated API calls
     cache: Arc<Mutex<Option<Vec<BCBranch>>>>,
+    // PR lookups from earlier runs, read when loading
+    pr_cache: PrCache,
 }
 
 #[cfg(feature = "github-api")]
 impl GitHubBranchStore {
     /// Creates a new GitHubBranchStore from a repository path
-    /// Note: Call `load().await` immediately after creation to fetch GitHub data
-    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
-        let git = GitRepository::open(path)?;
-
-        // Parse GitHub repo info from remote
-        let remote_url = git.get_origin_url()?;
-        let (owner, repo) = crate::git::parse_github_remote(&remote_url)?;
+    /// Branches are managed on the configured remote (`origin` by default); when an `upstream`
+    /// remote points to another repository on the same host, PRs are looked up there.
+    /// The default branch is asked from GitHub, falling back to `<remote>/HEAD`, and is always protected
+    /// Note: Call `load()` immediately after creation to fetch GitHub data
+    pub async fn new(path: impl AsRef<Path>, config: &Config) -> Result<Self> {
+        let git = GitRepository::open(path)?
+            .with_protection(config.protection_rules()?)
+            .with_remote(config.defaults.remote.as_deref().unwrap_or(DEFAULT_REMOTE));
+
+        // Parse GitHub host and repo info from the remotes
+        let head = crate::git::parse_github_remote(&git.remote_url(git.remote())?)?;
+        let GitHubRemote { host, owner, repo } = git
+            .upstream_remote()
+            .and_then(|upstream| git.remote_url(upstream).ok())
+            .and_then(|url| crate::git::parse_github_remote(&url).ok())
+            .filter(|upstream| upstream.host == head.host)
+            .unwrap_or_else(|| head.clone());
+        let host = GitHubHost::new(&host).with_config(&config.host(&host));
+        let ttl = config
+            .github
+            .cache_ttl
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_TTL);
+        let pr_cache = git.pr_cache(&owner, &repo, ttl);
 
         // Try to create authenticated client, fall back to offline
-        let github = match GitHubClient::from_env(owner.clone(), repo.clone()) {
+        let github = match GitHubClient::connect(&host, owner.clone(), repo.clone()) {
             Ok(client) => client,
-            Err(_) => {
-                eprintln!("Warning: GITHUB_TOKEN not found. PR status will show as 'No PR'.");
+            Err(e @ BranchCleanerError::TokenNotFound) => {
+                eprintln!(
+                    "Warning: {} (host {}). PR status will show as unknown.",
+                    e,
+                    host.name()
+                );
                 GitHubClient::offline(owner, repo)
             }
+            Err(e) => return Err(e),
+        }
+        .with_head_repo(head.owner, head.repo)
+        .with_concurrency(config.github.concurrency.unwrap_or(DEFAULT_CONCURRENCY));
+
+        let default_branch = match github.default_branch().await {
+            Ok(Some(name)) => Some(name),
+            Ok(None) | Err(_) => git.local_default_branch(),
         };
+        let git = git.with_default_branch(default_branch);
 
         Ok(Self {
             git,
             github,
+            scope: BranchScope::Local,
             cache: Arc::new(Mutex::new(None)),
+            pr_cache,
         })
     }
 
+    /// Switches between managing local branches and branches on the remote
+    pub fn with_scope(mut self, scope: BranchScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    /// Builds a LOADING branch with everything git knows about it (tip, last commit, upstream)
+    fn git_branch(&self, name: &str) -> BCBranch {
+        match self.scope {
+            BranchScope::Local => self
+                .loading_branch(name, self.git.branch_tip(name).ok().flatten())
+                .with_upstream(self.git.upstream_status(name).ok().flatten()),
+            BranchScope::Remote => self.loading_branch(name, self.git.remote_branch_tip(name)),
+        }
+    }
+
+    /// A LOADING branch pointing at `tip`, with the tip commit's details when git has it
+    fn loading_branch(&self, name: &str, tip: Option<String>) -> BCBranch {
+        let last_commit = tip.as_deref().and_then(|sha| self.git.commit_info(sha));
+        BCBranch::new(name, PrStatus::LOADING)
+            .with_tip_sha(tip)
+            .with_last_commit(last_commit)
+    }
+
+    /// Lists the branches in scope as LOADING branches
+    /// Remote branches come from the GitHub branches API, or from
+    /// `refs/remotes/<remote>/*` when offline or the request fails
+    async fn initial_branches(&self) -> Result<Vec<BCBranch>> {
+        if self.scope == BranchScope::Remote {
+            if let Ok(Some(branches)) = self.github.list_branches().await {
+                return Ok(branches
+                    .into_iter()
+                    .filter(|(name, _)| !self.git.is_protected(name))
+                    .map(|(name, sha)| self.loading_branch(&name, Some(sha)))
+                    .collect());
+            }
+        }
+
+        let names = match self.scope {
+            // Fast, no API calls
+            BranchScope::Local => self.git.list_local_branches()?,
+            BranchScope::Remote => self.git.list_remote_branches()?,
+        };
+        Ok(names.iter().map(|name| self.git_branch(name)).collect())
+    }
+
     /// Loads branches from git and starts async PR enrichment
     /// Returns immediately with branches in LOADING state + a receiver for streaming updates
-    pub fn load(&self) -> Result<(Vec<BCBranch>, UnboundedReceiver<BCBranch>)> {
-        // Get local branches from git (fast, no API calls)
-        let branch_names = self.git.list_local_branches()?;
-
+    pub async fn load(&self) -> Result<(Vec<BCBranch>, UnboundedReceiver<BCBranch>)> {
         // Create initial branches with LOADING status
-        let initial_branches: Vec<BCBranch> = branch_names
-            .iter()
-            .map(|name| BCBranch::new(name, PrStatus::LOADING))
-            .collect();
+        let initial_branches = self.initial_branches().await?;
 
         // Update cache with loading state
         *self.cache.lock().unwrap() = Some(initial_branches.clone());
@@ -147,11 +367,36 @@ impl GitHubBranchStore {
 
         // Clone what we need for the spawned task
         let github = self.github.clone();
+        let git = self.git.clone();
+        let scope = self.scope;
         let cache = Arc::clone(&self.cache);
+        let mut pr_cache = self.pr_cache.clone();
 
         // Spawn async task to fetch PR data - streams each branch as it's enriched
+        let to_enrich = initial_branches.clone();
         tokio::spawn(async move {
-            let enriched = github.enrich_branches_streaming(branch_names, tx).await;
+            // PR lookups stream through an inner channel so branches without a PR
+            // can be checked against local git history before reaching the TUI
+            let (pr_tx, mut pr_rx) = mpsc::unbounded_channel();
+            let lookup = tokio::spawn(async move {
+                github
+                    .enrich_branches_cached(to_enrich, &mut pr_cache, pr_tx)
+                    .await;
+                // Best effort: without a cache the next run looks everything up again.
+                // Offline runs learn nothing new, so the file is left alone
+                if !github.is_offline() {
+                    let _ = pr_cache.save();
+                }
+            });
+
+            let mut enriched = Vec::new();
+            while let Some(branch) = pr_rx.recv().await {
+                let branch = with_local_merge_status(&git, scope, branch);
+                // Send immediately to TUI (ignore error if receiver dropped)
+                let _ = tx.send(branch.clone());
+                enriched.push(branch);
+            }
+            let _ = lookup.await;
 
             // Update cache with final state
             *cache.lock().unwrap() = Some(enriched);
@@ -159,6 +404,107 @@ impl GitHubBranchStore {
 
         Ok((initial_branches, rx))
     }
+
+    /// Deletes branches on GitHub through the refs API, journaling them first
+    /// Protected branches, heads of open PRs and branches whose PR status is not known are refused
+    async fn delete_github_branches(&self, names: &[String]) -> Vec<DeletionResult> {
+        let cached = self.list_branches();
+        let mut results = Vec::new();
+        let mut entries = Vec::new();
+        for name in names {
+            let branch = cached.iter().find(|b| &b.name == name);
+            let refusal = match branch.map(|b| &b.pr_status) {
+                _ if self.git.is_protected(name) => Some(format!("{} is protected", name)),
+                Some(PrStatus::OPEN) => Some(format!("{} is the head of an open PR", name)),
+                Some(PrStatus::LOADING) => Some(format!("PR status of {} is still loading", name)),
+                Some(PrStatus::ERROR(_)) => {
+                    Some(format!("PR status of {} could not be loaded", name))
+                }
+                Some(PrStatus::UNKNOWN) => {
+                    Some(format!("PR status of {} is unknown (offline)", name))
+                }
+                _ => None,
+            };
+            let tip = branch.and_then(|b| b.tip_sha.clone());
+            match (refusal, tip) {
+                (Some(reason), _) => results.push(DeletionResult::new(
+                    name,
+                    DeletionOutcome::Failed(BranchCleanerError::RemoteDeleteRefused(reason)),
+                )),
+                (None, None) => results.push(DeletionResult::new(name, DeletionOutcome::Skipped)),
+                (None, Some(tip)) => entries.push(JournalEntry {
+                    name: name.clone(),
+                    tip,
+                    upstream_remote: None,
+                    upstream_merge: None,
+                }),
+            }
+        }
+
+        if let Err(e) = self.git.remote_journal().record(entries.clone()) {
+            // Without a journal entry there is no undo, so delete nothing
+            let message = e.to_string();
+            results.extend(entries.iter().map(|entry| {
+                DeletionResult::new(
+                    &entry.name,
+                    DeletionOutcome::Failed(BranchCleanerError::JournalWriteFailed(
+                        message.clone(),
+                    )),
+                )
+            }));
+            entries.clear();
+        }
+
+        for entry in entries {
+            let outcome = match self.github.delete_branch(&entry.name).await {
+                Ok(()) => {
+                    self.git.prune_remote_ref(&entry.name);
+                    DeletionOutcome::Deleted
+                }
+                Err(e) => DeletionOutcome::Failed(e),
+            };
+            results.push(DeletionResult::new(&entry.name, outcome));
+        }
+
+        // One result per requested name, in the same order
+        names
+            .iter()
+            .filter_map(|name| {
+                let index = results.iter().position(|r| &r.name == name)?;
+                Some(results.remove(index))
+            })
+            .collect()
+    }
+
+    /// Recreates the most recently deleted batch of GitHub branches from the remote journal
+    /// Returns the recreated branches, and the error for those that could not be recreated:
+    /// they stay in the journal, so undoing again retries just them
+    async fn restore_github_branches(&self) -> Result<(Vec<String>, Option<BranchCleanerError>)> {
+        let journal = self.git.remote_journal();
+        let batch = match journal.last_batch()? {
+            Some(batch) => batch,
+            None => return Ok((Vec::new(), None)),
+        };
+
+        let mut restored = Vec::new();
+        let mut remaining = Vec::new();
+        let mut failures = Vec::new();
+        for entry in batch.branches {
+            match self.github.create_branch(&entry.name, &entry.tip).await {
+                Ok(()) => restored.push(entry.name),
+                Err(e) => {
+                    failures.push(format!("{}: {}", entry.name, e));
+                    remaining.push(entry);
+                }
+            }
+        }
+
+        // Only forget the branches that are back
+        journal.retain_in_last_batch(remaining)?;
+        let failure =
+            (!failures.is_empty()).then(|| BranchCleanerError::RestoreFailed(failures.join(", ")));
+        Ok((restored, failure))
+    }
 }
 
 #[cfg(feature = "github-api")]
@@ -173,18 +519,149 @@ impl BranchStore for GitHubBranchStore {
             .unwrap_or_default()
     }
 
-    fn delete_branches(&mut self, names: &[String]) {
-        // Delete from git
-        if let Err(e) = self.git.delete_branches(names) {
-            eprintln!("Error deleting branches: {}", e);
-            return;
+    async fn delete_branches(&mut self, names: &[String]) -> Vec<DeletionResult> {
+        let results = match self.scope {
+            // Delete from git
+            BranchScope::Local => self.git.delete_branches(names),
+            BranchScope::Remote => self.delete_github_branches(names).await,
+        };
+
+        // Update cache by removing branches that are gone (failed ones stay)
+        if let Some(ref mut branches) = *self.cache.lock().unwrap() {
+            branches.retain(|b| {
+                !results
+                    .iter()
+                    .any(|r| r.name == b.name && !matches!(r.outcome, DeletionOutcome::Failed(_)))
+            });
+        }
+
+        results
+    }
+
+    fn delete_remote_branches(&mut self, names: &[String]) -> Vec<DeletionResult> {
+        if self.scope == BranchScope::Remote {
+            // Listed branches already are the ones on the remote: `delete_branches` removes them
+            return names
+                .iter()
+                .map(|name| DeletionResult::new(name, DeletionOutcome::Skipped))
+                .collect();
+        }
+        let results = self.git.delete_remote_branches(names);
+
+        // Branches still listed now track a branch that is gone
+        if let Some(ref mut branches) = *self.cache.lock().unwrap() {
+            for branch in branches.iter_mut() {
+                let deleted = results.iter().any(|r| {
+                    r.name == branch.name && matches!(r.outcome, DeletionOutcome::Deleted)
+                });
+                if deleted {
+                    branch.upstream = self.git.upstream_status(&branch.name).ok().flatten();
+                }
+            }
+        }
+
+        results
+    }
+
+    fn base_branch(&self) -> Option<String> {
+        self.git.default_branch().map(|name| name.to_owned())
+    }
+
+    fn scope(&self) -> BranchScope {
+        self.scope
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        self.github.rate_limit()
+    }
+
+    fn token_source(&self) -> Option<String> {
+        Some(match self.github.token_source() {
+            Some(source) => source.to_string(),
+            None => "none (offline)".to_string(),
+        })
+    }
+
+    async fn retry_pr_lookup(&mut self, name: &str) -> Option<BCBranch> {
+        if !self.list_branches().iter().any(|b| b.name == name) {
+            return None;
+        }
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let branch = self.git_branch(name);
+        let enriched = self
+            .github
+            .enrich_branches_streaming(vec![branch], tx)
+            .await;
+        let refreshed =
+            with_local_merge_status(&self.git, self.scope, enriched.into_iter().next()?);
+
+        if let Some(ref mut branches) = *self.cache.lock().unwrap() {
+            if let Some(cached) = branches.iter_mut().find(|b| b.name == name) {
+                *cached = refreshed.clone();
+            }
         }
+        Some(refreshed)
+    }
+
+    async fn restore_deleted_branches(&mut self) -> Result<Vec<String>> {
+        let (names, failure) = match self.scope {
+            BranchScope::Local => (self.git.restore_last_deletion()?, None),
+            BranchScope::Remote => self.restore_github_branches().await?,
+        };
+
+        // Look up PR status for the restored branches before returning them:
+        // the streaming channel from load() belongs to the initial enrichment.
+        let restored: Vec<BCBranch> = names.iter().map(|name| self.git_branch(name)).collect();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let enriched = self.github.enrich_branches_streaming(restored, tx).await;
+        let enriched = enriched
+            .into_iter()
+            .map(|branch| with_local_merge_status(&self.git, self.scope, branch));
 
-        // Update cache by removing deleted branches
         if let Some(ref mut branches) = *self.cache.lock().unwrap() {
-            branches.retain(|b| !names.contains(&b.name));
+            branches.extend(enriched);
+            branches.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(names),
+        }
+    }
+}
+
+/// Marks branches without a PR as MERGED_LOCALLY when git history shows
+/// their work already reached the default branch (works without a token),
+/// and merged branches with commits on top of the merged PR head as MERGED_WITH_CHANGES
+#[cfg(feature = "github-api")]
+fn with_local_merge_status(
+    git: &GitRepository,
+    scope: BranchScope,
+    mut branch: BCBranch,
+) -> BCBranch {
+    let merged_locally = matches!(branch.pr_status, PrStatus::NONE | PrStatus::UNKNOWN)
+        && git.default_branch().is_some_and(|base| {
+            let status = match scope {
+                BranchScope::Local => git.local_merge_status(&branch.name, base),
+                BranchScope::Remote => git.remote_merge_status(&branch.name, base),
+            };
+            matches!(status, Ok(Some(_)))
+        });
+    if merged_locally {
+        branch.pr_status = PrStatus::MERGED_LOCALLY;
+    }
+
+    // Work committed after the PR was merged would be lost with the branch
+    if branch.pr_status == PrStatus::MERGED {
+        let merged_head = PrSummary::deciding(&branch.prs).and_then(|pr| pr.head_sha.as_deref());
+        if let (Some(tip), Some(merged_head)) = (branch.tip_sha.as_deref(), merged_head) {
+            if let Ok(Some(count @ 1..)) = git.commits_after(tip, merged_head, git.default_branch())
+            {
+                branch.pr_status = PrStatus::MERGED_WITH_CHANGES(count);
+            }
         }
     }
+    branch
 }
 
 #[cfg(feature = "github-api")]
@@ -205,15 +682,183 @@ mod tests {
         assert!(!branches.is_empty());
     }
 
-    #[test]
-    fn in_memory_store_can_delete_branches() {
+    #[tokio::test]
+    async fn in_memory_store_can_delete_branches() {
         let mut store = InMemoryBranchStore::default();
         let initial_count = store.list_branches().len();
 
-        store.delete_branches(&["main".to_string()]);
+        let results = store
+            .delete_branches(&["main".to_string(), "missing".to_string()])
+            .await;
 
+        assert!(matches!(results[0].outcome, DeletionOutcome::Deleted));
+        assert!(matches!(results[1].outcome, DeletionOutcome::Skipped));
         let remaining = store.list_branches();
         assert_eq!(remaining.len(), initial_count - 1);
         assert!(!remaining.iter().any(|b| b.name == "main"));
     }
+
+    #[tokio::test]
+    async fn in_memory_store_restores_last_deleted_batch() {
+        let mut store = InMemoryBranchStore::new(vec![
+            BCBranch::new("a", PrStatus::NONE),
+            BCBranch::new("b", PrStatus::MERGED),
+            BCBranch::new("c", PrStatus::MERGED),
+        ]);
+        store.delete_branches(&["a".to_string()]).await;
+        store
+            .delete_branches(&["b".to_string(), "c".to_string()])
+            .await;
+
+        let restored = store.restore_deleted_branches().await.unwrap();
+
+        assert_eq!(restored, vec!["b".to_string(), "c".to_string()]);
+        let names: Vec<String> = store.list_branches().into_iter().map(|b| b.name).collect();
+        assert_eq!(names, vec!["b".to_string(), "c".to_string()]);
+    }
+
+    /// Creates a repository whose `feature` branch has a commit of its own,
+    /// so git history cannot tell it was merged into `main`
+    #[cfg(feature = "github-api")]
+    fn init_repo(dir: &Path) -> GitRepository {
+        let repo = git2::Repository::init(dir).unwrap();
+        let sig = git2::Signature::now("Test", "test@example.com").unwrap();
+        let tree = repo
+            .find_tree(repo.index().unwrap().write_tree().unwrap())
+            .unwrap();
+        let main = repo
+            .commit(Some("refs/heads/main"), &sig, &sig, "initial", &tree, &[])
+            .unwrap();
+        repo.set_head("refs/heads/main").unwrap();
+        let main = repo.find_commit(main).unwrap();
+        let mut builder = repo.treebuilder(Some(&tree)).unwrap();
+        let blob = repo.blob(b"work").unwrap();
+        builder.insert("work.txt", blob, 0o100644).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        repo.commit(
+            Some("refs/heads/feature"),
+            &sig,
+            &sig,
+            "work",
+            &tree,
+            &[&main],
+        )
+        .unwrap();
+
+        GitRepository::open(dir)
+            .unwrap()
+            .with_default_branch(Some("main".to_string()))
+    }
+
+    #[cfg(feature = "github-api")]
+    fn github_store(
+        git: GitRepository,
+        github: GitHubClient,
+        scope: BranchScope,
+    ) -> GitHubBranchStore {
+        let pr_cache = git.pr_cache("owner", "repo", DEFAULT_TTL);
+        GitHubBranchStore {
+            git,
+            github,
+            scope,
+            cache: Arc::new(Mutex::new(None)),
+            pr_cache,
+        }
+    }
+
+    #[cfg(feature = "github-api")]
+    #[tokio::test]
+    async fn offline_store_refuses_remote_deletion_of_unknown_branches() {
+        use crate::view_model::BranchViewModel;
+
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let store = github_store(
+            init_repo(dir.path()),
+            GitHubClient::offline("owner".to_string(), "repo".to_string()),
+            BranchScope::Local,
+        );
+        let (_, mut rx) = store.load().await.unwrap();
+        while rx.recv().await.is_some() {}
+        let mut vm = BranchViewModel::new(store).with_delete_remote(true);
+        let mut state = vm.load_initial_state();
+        state.selected_branches = vec!["feature".to_string()];
+
+        // Act
+        let report = vm.delete_selected_branches(&mut state).await;
+
+        // Assert
+        let feature = report.iter().find(|e| e.name == "feature").unwrap();
+        assert!(matches!(
+            feature.remote_outcome,
+            Some(DeletionOutcome::Failed(
+                BranchCleanerError::RemoteDeleteRefused(_)
+            ))
+        ));
+    }
+
+    #[cfg(feature = "github-api")]
+    #[tokio::test]
+    async fn remote_restore_keeps_going_and_remembers_what_failed() {
+        use crate::auth::{Token, TokenSource};
+        use crate::config::HostConfig;
+        use wiremock::matchers::{body_string_contains, method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // Arrange: GitHub recreates `a` but refuses `b`
+        let server = MockServer::start().await;
+        let refs = "/api/v3/repos/owner/repo/git/refs";
+        Mock::given(method("POST"))
+            .and(path(refs))
+            .and(body_string_contains("refs/heads/a"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "ref": "refs/heads/a",
+                "node_id": "1",
+                "url": format!("{}{}/heads/a", server.uri(), refs),
+                "object": { "type": "commit", "sha": "0".repeat(40), "url": server.uri() }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path(refs))
+            .and(body_string_contains("refs/heads/b"))
+            .respond_with(ResponseTemplate::new(422).set_body_json(serde_json::json!({
+                "message": "Reference already exists",
+                "documentation_url": "https://docs.github.com"
+            })))
+            .mount(&server)
+            .await;
+        let host = GitHubHost::new("github.example.com").with_config(&HostConfig {
+            api_url: Some(format!("{}/api/v3", server.uri())),
+            token: None,
+        });
+        let token = Token {
+            value: "secret".to_string(),
+            source: TokenSource::Config,
+        };
+        let github =
+            GitHubClient::new(&host, "owner".to_string(), "repo".to_string(), token).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let git = init_repo(dir.path());
+        let entry = |name: &str| JournalEntry {
+            name: name.to_owned(),
+            tip: "0".repeat(40),
+            upstream_remote: None,
+            upstream_merge: None,
+        };
+        git.remote_journal()
+            .record(vec![entry("a"), entry("b")])
+            .unwrap();
+        let journal = git.remote_journal();
+        let mut store = github_store(git, github, BranchScope::Remote);
+
+        // Act
+        let result = store.restore_deleted_branches().await;
+
+        // Assert: `b` stays in the journal, so undoing again retries just that one
+        assert!(matches!(result, Err(BranchCleanerError::RestoreFailed(_))));
+        let batch = journal.last_batch().unwrap().unwrap();
+        assert_eq!(batch.branches, vec![entry("b")]);
+    }
 }
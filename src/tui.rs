@@ -1,17 +1,18 @@
 use ratatui::{
     crossterm::event::{self, Event, KeyCode, KeyEventKind},
-    layout::{Constraint, Layout},
+    layout::{Constraint, Flex, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 use std::time::Duration;
 use tokio::sync::mpsc::UnboundedReceiver;
 
-use crate::store::BranchStore;
-use crate::view_model::{BranchViewModel, ViewState};
-use crate::{BCBranch, PrStatus};
+use crate::policy::SelectionRule;
+use crate::store::{BranchScope, BranchStore, DeletionOutcome};
+use crate::view_model::{BranchViewModel, DeletionReport, StatusFilter, ViewState};
+use crate::{unix_now, BCBranch, PrStatus, UpstreamStatus};
 
 /// Configuration for animation timing
 #[derive(Debug, Clone, Copy)]
@@ -38,12 +39,16 @@ impl AnimationConfig {
 }
 
 /// Maps PR status to display colors (with animation frame for shimmer)
-fn get_status_color(status: PrStatus, animation_frame: u8) -> Color {
+fn get_status_color(status: &PrStatus, animation_frame: u8) -> Color {
     match status {
-        PrStatus::MERGED => Color::Green,   // Safe to delete
-        PrStatus::OPEN => Color::Yellow,    // Caution - active PR
-        PrStatus::CLOSED => Color::Red,     // Closed without merging
-        PrStatus::NONE => Color::White,     // Default
+        PrStatus::MERGED => Color::Green,              // Safe to delete
+        PrStatus::OPEN => Color::Yellow,               // Caution - active PR
+        PrStatus::CLOSED => Color::Red,                // Closed without merging
+        PrStatus::NONE => Color::White,                // Default
+        PrStatus::MERGED_LOCALLY => Color::LightGreen, // Merged per git history
+        PrStatus::MERGED_WITH_CHANGES(_) => Color::LightYellow, // Later commits would be lost
+        PrStatus::ERROR(_) => Color::LightMagenta,     // Lookup failed - status unknown
+        PrStatus::UNKNOWN => Color::Blue,              // Not looked up (offline)
         PrStatus::LOADING => {
             // Shimmer effect: cycle through grays
             match animation_frame % 4 {
@@ -57,12 +62,18 @@ fn get_status_color(status: PrStatus, animation_frame: u8) -> Color {
 }
 
 /// Formats PR status for display in the TUI (with animation frame for loading dots)
-fn format_status_for_display(status: PrStatus, animation_frame: u8) -> String {
+fn format_status_for_display(status: &PrStatus, animation_frame: u8) -> String {
     match status {
         PrStatus::OPEN => "OPEN".to_string(),
         PrStatus::MERGED => "MERGED ✓".to_string(),
         PrStatus::CLOSED => "CLOSED ✗".to_string(),
         PrStatus::NONE => "No PR".to_string(),
+        PrStatus::MERGED_LOCALLY => "MERGED (local) ✓".to_string(),
+        PrStatus::MERGED_WITH_CHANGES(count) => {
+            format!("MERGED ⚠ {} commit(s) after the merge would be lost", count)
+        }
+        PrStatus::ERROR(reason) => format!("ERROR ⚠ {} (r: retry)", reason),
+        PrStatus::UNKNOWN => "UNKNOWN (offline)".to_string(),
         PrStatus::LOADING => {
             // Animate dots: Loading -> Loading. -> Loading.. -> Loading...
             let dots = ".".repeat((animation_frame % 4) as usize);
@@ -71,6 +82,90 @@ fn format_status_for_display(status: PrStatus, animation_frame: u8) -> String {
     }
 }
 
+/// Popup drawn on top of the branch list, capturing all key presses while open
+enum Modal {
+    /// Asks before deleting the listed branches; only `y` confirms
+    ConfirmDelete(Vec<BCBranch>),
+    /// Result of a dry run: what would have been deleted and why
+    DryRunReport(Vec<DeletionReport>),
+}
+
+/// Store operation started by a key press; it runs once the next frame shows its progress notice
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pending {
+    Delete,
+    Restore,
+    RetryLookup,
+}
+
+/// Message shown in the status line after an action completes
+struct Notification {
+    message: String,
+    is_error: bool,
+}
+
+impl Notification {
+    fn info(message: String) -> Self {
+        Self {
+            message,
+            is_error: false,
+        }
+    }
+
+    fn error(message: String) -> Self {
+        Self {
+            message,
+            is_error: true,
+        }
+    }
+
+    /// Summarizes per-branch deletion outcomes, naming every failure
+    fn from_deletion_report(report: &[DeletionReport]) -> Self {
+        let mut deleted = 0;
+        let mut skipped = Vec::new();
+        let mut failed = Vec::new();
+        let mut remote_deleted = 0;
+        let mut remote_failed = Vec::new();
+        for entry in report {
+            match &entry.outcome {
+                Some(DeletionOutcome::Deleted) => deleted += 1,
+                Some(DeletionOutcome::Skipped) => skipped.push(entry.name.as_str()),
+                Some(DeletionOutcome::Failed(e)) => failed.push(format!("{} ({})", entry.name, e)),
+                None => {}
+            }
+            match &entry.remote_outcome {
+                Some(DeletionOutcome::Deleted) => remote_deleted += 1,
+                Some(DeletionOutcome::Failed(e)) => {
+                    remote_failed.push(format!("{} ({})", entry.name, e))
+                }
+                Some(DeletionOutcome::Skipped) | None => {}
+            }
+        }
+
+        let mut message = format!("Deleted {} branch(es)", deleted);
+        if report.iter().any(|e| e.remote) {
+            message.push_str(&format!(", {} on origin", remote_deleted));
+        }
+        if !skipped.is_empty() {
+            message.push_str(&format!(" | Skipped (not found): {}", skipped.join(", ")));
+        }
+        if !failed.is_empty() {
+            message.push_str(&format!(" | Failed: {}", failed.join(", ")));
+        }
+        if !remote_failed.is_empty() {
+            message.push_str(&format!(
+                " | Failed on origin: {}",
+                remote_failed.join(", ")
+            ));
+        }
+
+        Self {
+            message,
+            is_error: !failed.is_empty() || !remote_failed.is_empty(),
+        }
+    }
+}
+
 /// App structure holds the application state
 struct App<T: BranchStore> {
     view_state: ViewState,
@@ -79,26 +174,33 @@ struct App<T: BranchStore> {
     animation_frame: u8,
     animation_config: AnimationConfig,
     update_rx: UnboundedReceiver<BCBranch>,
+    modal: Option<Modal>,
+    notification: Option<Notification>,
+    /// Typing a search query after `/`: key presses edit the query
+    searching: bool,
+    /// Store operation to run after the next draw
+    pending: Option<Pending>,
 }
 
 impl<T: BranchStore> App<T> {
     fn new(
-        store: T,
+        view_model: BranchViewModel<T>,
         update_rx: UnboundedReceiver<BCBranch>,
         animation_config: AnimationConfig,
     ) -> Self {
-        let view_model = BranchViewModel::new(store);
         let view_state = view_model.load_initial_state();
-        let mut list_state = ListState::default();
-        list_state.select(Some(0));
 
         Self {
             view_state,
-            list_state,
+            list_state: ListState::default(),
             view_model,
             animation_frame: 0,
             animation_config,
             update_rx,
+            modal: None,
+            notification: None,
+            searching: false,
+            pending: None,
         }
     }
 
@@ -116,42 +218,177 @@ impl<T: BranchStore> App<T> {
                 return false;
             }
 
+            // An open popup captures the key press: `y` confirms deletion, anything else closes it
+            if let Some(modal) = self.modal.take() {
+                if matches!(modal, Modal::ConfirmDelete(_)) && key.code == KeyCode::Char('y') {
+                    self.start(Pending::Delete);
+                }
+                return false;
+            }
+
+            if self.searching {
+                self.handle_search_key(key.code);
+                return false;
+            }
+
             match key.code {
                 KeyCode::Char('q') => return true,
-                KeyCode::Up => {
-                    self.view_model.move_up(&mut self.view_state);
-                    self.list_state.select(Some(self.view_state.selected_index));
-                }
-                KeyCode::Down => {
-                    self.view_model.move_down(&mut self.view_state);
-                    self.list_state.select(Some(self.view_state.selected_index));
-                }
+                KeyCode::Up => self.view_model.move_up(&mut self.view_state),
+                KeyCode::Down => self.view_model.move_down(&mut self.view_state),
+                KeyCode::Char('/') => self.searching = true,
+                KeyCode::Char('m') => self
+                    .view_model
+                    .toggle_status_filter(&mut self.view_state, StatusFilter::Merged),
+                KeyCode::Char('o') => self
+                    .view_model
+                    .toggle_status_filter(&mut self.view_state, StatusFilter::Open),
+                KeyCode::Char('n') => self
+                    .view_model
+                    .toggle_status_filter(&mut self.view_state, StatusFilter::NoPr),
+                KeyCode::Esc => self.view_model.clear_filter(&mut self.view_state),
                 KeyCode::Char(' ') => {
                     // Toggle selection
                     self.view_model.toggle_selection(&mut self.view_state);
                 }
-                KeyCode::Char('d') => {
-                    // Delete selected branches
-                    if !self.view_state.selected_branches.is_empty() {
-                        self.view_model
-                            .delete_selected_branches(&mut self.view_state);
-                        self.list_state.select(Some(self.view_state.selected_index));
+                KeyCode::Char('d') if !self.view_state.selected_branches.is_empty() => {
+                    if self.view_model.is_dry_run() {
+                        // Nothing is deleted, so there is nothing to confirm
+                        self.start(Pending::Delete);
+                    } else {
+                        let to_delete = self
+                            .view_state
+                            .branches
+                            .iter()
+                            .filter(|b| self.view_state.selected_branches.contains(&b.name))
+                            .cloned()
+                            .collect();
+                        self.modal = Some(Modal::ConfirmDelete(to_delete));
                     }
                 }
+                KeyCode::Char('R') => {
+                    // Switch deleting branches on origin as well
+                    self.view_model.toggle_delete_remote();
+                    self.notification =
+                        Some(Notification::info(if self.view_model.deletes_remote() {
+                            "Deletions will also remove branches on origin".to_string()
+                        } else {
+                            "Deletions are local only".to_string()
+                        }));
+                }
+                KeyCode::Char('u') => {
+                    // Undo the most recent deletion (works across sessions)
+                    self.start(Pending::Restore);
+                }
+                KeyCode::Char('r') => {
+                    // Look up the PR of the current branch again after a failure
+                    self.start(Pending::RetryLookup);
+                }
+                KeyCode::Char('p') if !self.view_state.selected_branches.is_empty() => {
+                    // Preview what 'd' would delete
+                    let report = self.view_model.preview_deletion(&self.view_state);
+                    self.modal = Some(Modal::DryRunReport(report));
+                }
                 _ => {}
             }
         }
         false
     }
+
+    /// Edits the search query: Enter keeps it, Esc clears it
+    fn handle_search_key(&mut self, code: KeyCode) {
+        let mut query = self.view_state.filter.query.clone();
+        match code {
+            KeyCode::Enter => self.searching = false,
+            KeyCode::Esc => {
+                self.searching = false;
+                query.clear();
+            }
+            KeyCode::Backspace => {
+                query.pop();
+            }
+            KeyCode::Char(c) => query.push(c),
+            KeyCode::Up => self.view_model.move_up(&mut self.view_state),
+            KeyCode::Down => self.view_model.move_down(&mut self.view_state),
+            _ => {}
+        }
+        if query != self.view_state.filter.query {
+            self.view_model.set_query(&mut self.view_state, query);
+        }
+    }
+
+    /// Queues a store operation and says it is underway
+    fn start(&mut self, pending: Pending) {
+        let message = match pending {
+            Pending::Delete => "Deleting...",
+            Pending::Restore => "Restoring...",
+            Pending::RetryLookup => "Looking up PR...",
+        };
+        self.notification = Some(Notification::info(message.to_string()));
+        self.pending = Some(pending);
+    }
+
+    /// Runs the queued store operation, if any
+    async fn run_pending(&mut self) {
+        match self.pending.take() {
+            Some(Pending::Delete) => self.delete_selected().await,
+            Some(Pending::Restore) => self.restore_deleted().await,
+            Some(Pending::RetryLookup) => self.retry_lookup().await,
+            None => {}
+        }
+    }
+
+    /// Restores the most recently deleted batch and reports the result
+    async fn restore_deleted(&mut self) {
+        let result = self
+            .view_model
+            .restore_deleted_branches(&mut self.view_state)
+            .await;
+        self.notification = Some(match result {
+            Ok(restored) if restored.is_empty() => {
+                Notification::info("Nothing to restore".to_string())
+            }
+            Ok(restored) => Notification::info(format!("Restored: {}", restored.join(", "))),
+            Err(e) => Notification::error(format!("Restore failed: {}", e)),
+        });
+    }
+
+    /// Retries the failed PR lookup of the branch under the cursor and reports the result
+    async fn retry_lookup(&mut self) {
+        self.notification = match self.view_model.retry_lookup(&mut self.view_state).await {
+            Some(branch) => Some(match &branch.pr_status {
+                PrStatus::ERROR(reason) => Notification::error(format!(
+                    "Lookup for {} failed again: {}",
+                    branch.name, reason
+                )),
+                status => Notification::info(format!("{}: {}", branch.name, status)),
+            }),
+            None => Some(Notification::info("No failed lookup to retry".to_string())),
+        };
+    }
+
+    /// Deletes selected branches (only reported in dry-run mode)
+    async fn delete_selected(&mut self) {
+        let report = self
+            .view_model
+            .delete_selected_branches(&mut self.view_state)
+            .await;
+        if self.view_model.is_dry_run() {
+            self.modal = Some(Modal::DryRunReport(report));
+        } else {
+            self.notification = Some(Notification::from_deletion_report(&report));
+        }
+    }
 }
 
 /// Creates a ListItem for a branch with multi-line content
+/// `rule` is the auto-selection rule shown next to a selected branch
 fn create_branch_list_item(
     branch: &BCBranch,
     is_selected_for_deletion: bool,
+    rule: Option<SelectionRule>,
     animation_frame: u8,
 ) -> ListItem<'_> {
-    let color = get_status_color(branch.pr_status, animation_frame);
+    let color = get_status_color(&branch.pr_status, animation_frame);
     let mut lines = vec![];
 
     // Branch name line with selection checkbox
@@ -160,7 +397,7 @@ fn create_branch_list_item(
     } else {
         "[ ] "
     };
-    lines.push(Line::from(vec![
+    let mut name_spans = vec![
         Span::styled(
             checkbox,
             Style::default().fg(if is_selected_for_deletion {
@@ -173,62 +410,279 @@ fn create_branch_list_item(
             branch.name.clone(),
             Style::default().fg(color).add_modifier(Modifier::BOLD),
         ),
-    ]));
+    ];
+    if let Some(rule) = rule {
+        name_spans.push(Span::styled(
+            format!("  [{}]", rule),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    lines.push(Line::from(name_spans));
 
     // PR info line if available (not shown for LOADING status)
     if branch.pr_status != PrStatus::LOADING {
         if let (Some(pr_number), Some(pr_title)) = (branch.pr_number, &branch.pr_title) {
+            let others = match branch.prs.len() {
+                0 | 1 => String::new(),
+                count => format!(" ({} PRs)", count),
+            };
             lines.push(Line::from(vec![Span::styled(
-                format!("    └─ PR #{}: {}", pr_number, pr_title),
+                format!("    └─ PR #{}: {}{}", pr_number, pr_title, others),
                 Style::default().fg(Color::Gray),
             )]));
         }
     }
 
-    // Status line
-    lines.push(Line::from(vec![Span::styled(
+    // Last commit line: age, author, short SHA and subject
+    if let Some(commit) = &branch.last_commit {
+        let sha = branch.tip_sha.as_deref().unwrap_or_default();
+        lines.push(Line::from(vec![Span::styled(
+            format!(
+                "    └─ {} by {} <{}> · {} {}",
+                format_age(unix_now() as i64 - commit.time),
+                commit.author_name,
+                commit.author_email,
+                &sha[..sha.len().min(7)],
+                commit.subject
+            ),
+            Style::default().fg(Color::DarkGray),
+        )]));
+    }
+
+    // Status line, followed by the upstream state when the branch tracks one
+    let mut status_spans = vec![Span::styled(
         format!(
             "    └─ Status: {}",
-            format_status_for_display(branch.pr_status, animation_frame)
+            format_status_for_display(&branch.pr_status, animation_frame)
         ),
         Style::default().fg(color),
-    )]));
+    )];
+    if let Some(upstream) = &branch.upstream {
+        let upstream_color = match upstream {
+            UpstreamStatus::Gone { .. } => Color::Magenta,
+            UpstreamStatus::Tracking { .. } => Color::DarkGray,
+        };
+        status_spans.push(Span::styled(
+            format!("  ({})", upstream),
+            Style::default().fg(upstream_color),
+        ));
+    }
+    lines.push(Line::from(status_spans));
 
     ListItem::new(lines)
 }
 
+/// Formats how long ago a commit was made, in the largest whole unit (e.g. "3 days ago")
+fn format_age(seconds: i64) -> String {
+    const UNITS: [(i64, &str); 6] = [
+        (365 * 24 * 3600, "year"),
+        (30 * 24 * 3600, "month"),
+        (7 * 24 * 3600, "week"),
+        (24 * 3600, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ];
+    UNITS
+        .iter()
+        .find(|(length, _)| seconds >= *length)
+        .map(|(length, unit)| {
+            let count = seconds / length;
+            let plural = if count == 1 { "" } else { "s" };
+            format!("{} {}{} ago", count, unit, plural)
+        })
+        .unwrap_or_else(|| "just now".to_string())
+}
+
+/// Returns a rectangle centered in `area` taking the given percentages of its size
+fn centered_rect(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Percentage(percent_y)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(percent_x)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+/// Branches whose deletion could lose work: an open PR, no PR, or status still unknown
+fn needs_deletion_warning(status: &PrStatus) -> bool {
+    matches!(
+        status,
+        PrStatus::OPEN
+            | PrStatus::NONE
+            | PrStatus::LOADING
+            | PrStatus::ERROR(_)
+            | PrStatus::UNKNOWN
+            | PrStatus::MERGED_WITH_CHANGES(_)
+    )
+}
+
+/// Renders the deletion confirmation popup, highlighting risky branches
+fn render_confirm_delete(frame: &mut Frame, branches: &[BCBranch], delete_remote: bool) {
+    let area = centered_rect(frame.area(), 70, 60);
+
+    let mut lines: Vec<Line> = branches
+        .iter()
+        .map(|branch| {
+            let style = if needs_deletion_warning(&branch.pr_status) {
+                Style::default()
+                    .fg(Color::LightRed)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            Line::from(Span::styled(
+                format!("{} ({})", branch.name, branch.pr_status),
+                style,
+            ))
+        })
+        .collect();
+
+    let warnings = branches
+        .iter()
+        .filter(|b| needs_deletion_warning(&b.pr_status))
+        .count();
+    lines.push(Line::from(""));
+    if warnings > 0 {
+        lines.push(Line::from(Span::styled(
+            format!("Warning: {} branch(es) have an open PR or no PR", warnings),
+            Style::default().fg(Color::LightRed),
+        )));
+    }
+    if delete_remote {
+        lines.push(Line::from(Span::styled(
+            "Branches will also be deleted on origin (open PR heads are skipped; undo is local only)",
+            Style::default().fg(Color::LightRed),
+        )));
+    }
+    lines.push(Line::from(Span::styled(
+        "Press 'y' to delete, any other key to cancel.",
+        Style::default().fg(Color::Gray),
+    )));
+
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::bordered().title(format!(" Delete {} branch(es)? ", branches.len())));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
+/// Renders the dry-run report popup: one line per branch with its tip and selection reason
+fn render_dry_run_report(frame: &mut Frame, report: &[DeletionReport]) {
+    let area = centered_rect(frame.area(), 70, 60);
+
+    let mut lines: Vec<Line> = report
+        .iter()
+        .map(|entry| {
+            let sha = entry.tip_sha.as_deref().unwrap_or("unknown");
+            Line::from(vec![
+                Span::styled(
+                    entry.name.clone(),
+                    Style::default().add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" {}", &sha[..sha.len().min(7)]),
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled(
+                    format!(" - {}", entry.reason),
+                    Style::default().fg(Color::Gray),
+                ),
+                Span::styled(
+                    if entry.remote { " (+ origin)" } else { "" },
+                    Style::default().fg(Color::LightRed),
+                ),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "Nothing was deleted. Press any key to close.",
+        Style::default().fg(Color::Gray),
+    )));
+
+    let popup = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::bordered().title(format!(
+            " Dry run: {} branch(es) would be deleted ",
+            report.len()
+        )));
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(popup, area);
+}
+
 /// Renders the application UI
 fn render<T: BranchStore>(frame: &mut Frame, app: &mut App<T>) {
-    let [header_area, list_area, footer_area] = Layout::vertical([
+    let [header_area, list_area, status_area, footer_area] = Layout::vertical([
         Constraint::Length(3),
         Constraint::Fill(1),
+        Constraint::Length(1),
         Constraint::Length(3),
     ])
     .areas(frame.area());
 
     // Render header
-    let header = Paragraph::new("Branch Cleaner - Git Branch Manager")
-        .block(Block::bordered())
-        .style(
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD),
-        );
+    let mut title = "Branch Cleaner - Git Branch Manager".to_string();
+    if app.view_model.scope() == BranchScope::Remote {
+        title.push_str(" - branches on origin");
+    }
+    if let Some(base) = app.view_model.base_branch() {
+        title.push_str(&format!(" (base: {})", base));
+    }
+    if app.view_model.is_dry_run() {
+        title.push_str(" [DRY RUN]");
+    }
+    if app.view_model.deletes_remote() {
+        title.push_str(" [+ORIGIN]");
+    }
+    if let Some(source) = app.view_model.token_source() {
+        title.push_str(&format!(" | token: {}", source));
+    }
+    if let Some(limit) = app.view_model.rate_limit() {
+        title.push_str(&format!(" | API: {}/{}", limit.remaining, limit.limit));
+    }
+    let header = Paragraph::new(title).block(Block::bordered()).style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
     frame.render_widget(header, header_area);
 
-    // Render branch list
-    let items: Vec<ListItem> = app
-        .view_state
-        .branches
+    // Render the branches the filter shows, keeping the cursor on the current one
+    let visible = app.view_model.visible_indices(&app.view_state);
+    app.list_state.select(
+        visible
+            .iter()
+            .position(|&i| i == app.view_state.selected_index),
+    );
+    let items: Vec<ListItem> = visible
         .iter()
+        .map(|&i| &app.view_state.branches[i])
         .map(|b| {
             let is_selected = app.view_state.selected_branches.contains(&b.name);
-            create_branch_list_item(b, is_selected, app.animation_frame)
+            let rule = is_selected
+                .then(|| app.view_model.selection_rule(b))
+                .flatten();
+            create_branch_list_item(b, is_selected, rule, app.animation_frame)
         })
         .collect();
 
+    let filter = &app.view_state.filter;
+    let mut list_title = "Branches".to_string();
+    if filter.is_active() {
+        let mut parts = Vec::new();
+        if !filter.query.is_empty() {
+            parts.push(format!("/{}", filter.query));
+        }
+        if let Some(status) = filter.status {
+            parts.push(format!("{} only", status));
+        }
+        list_title.push_str(&format!(" (filter: {})", parts.join(", ")));
+    }
     let list = List::new(items)
-        .block(Block::bordered().title("Branches"))
+        .block(Block::bordered().title(list_title))
         .highlight_style(
             Style::default()
                 .bg(Color::Cyan)
@@ -239,17 +693,42 @@ fn render<T: BranchStore>(frame: &mut Frame, app: &mut App<T>) {
 
     frame.render_stateful_widget(list, list_area, &mut app.list_state);
 
+    // Render status line: the search being typed, or the result of the last action
+    if app.searching {
+        let prompt = Paragraph::new(format!(
+            "/{}█  (Enter: keep, Esc: clear)",
+            app.view_state.filter.query
+        ))
+        .style(Style::default().fg(Color::Cyan));
+        frame.render_widget(prompt, status_area);
+    } else if let Some(notification) = &app.notification {
+        let status = Paragraph::new(notification.message.as_str()).style(Style::default().fg(
+            if notification.is_error {
+                Color::LightRed
+            } else {
+                Color::Green
+            },
+        ));
+        frame.render_widget(status, status_area);
+    }
+
     // Render footer
     let selected_count = app.view_state.selected_branches.len();
-    let delete_msg = if selected_count > 0 {
+    let mut delete_msg = format!(
+        "Showing {}/{} | ",
+        visible.len(),
+        app.view_state.branches.len()
+    );
+    delete_msg.push_str(&if selected_count > 0 {
         format!("Selected: {} | Press 'd' to delete", selected_count)
     } else {
         "No branches selected".to_string()
-    };
+    });
+    delete_msg.push_str(&format!(" | Auto-select: {}", app.view_model.policy()));
 
     let footer_lines = vec![
         Line::from(Span::styled(
-            "Navigation: ↑↓ arrows | Space: select | d: delete | q: quit",
+            "Navigation: ↑↓ arrows | Space: select | d: delete | R: +origin | p: preview | u: undo | r: retry | /: search | m/o/n: merged/open/no PR only | Esc: show all | q: quit",
             Style::default().fg(Color::Gray),
         )),
         Line::from(Span::styled(
@@ -261,23 +740,31 @@ fn render<T: BranchStore>(frame: &mut Frame, app: &mut App<T>) {
             }),
         )),
         Line::from(Span::styled(
-            "Green = Merged | Yellow = Open PR | Red = Closed (not merged) | White = No PR",
+            "Green = Merged | Light green = Merged locally | Yellow = Open PR | Light yellow = Merged + new commits | Red = Closed | White = No PR | Blue = Unknown (offline) | Magenta = Error",
             Style::default().fg(Color::Gray),
         )),
     ];
     let footer = Paragraph::new(footer_lines);
     frame.render_widget(footer, footer_area);
+
+    match &app.modal {
+        Some(Modal::ConfirmDelete(branches)) => {
+            render_confirm_delete(frame, branches, app.view_model.deletes_remote())
+        }
+        Some(Modal::DryRunReport(report)) => render_dry_run_report(frame, report),
+        None => {}
+    }
 }
 
 /// Entry point to run the TUI application
-pub fn run_branch_tui<T: BranchStore>(
-    store: T,
+pub async fn run_branch_tui<T: BranchStore>(
+    view_model: BranchViewModel<T>,
     update_rx: UnboundedReceiver<BCBranch>,
     animation_config: AnimationConfig,
 ) -> Result<(), Box<dyn std::error::Error>> {
     // Initialize terminal
     let mut terminal = ratatui::init();
-    let mut app = App::new(store, update_rx, animation_config);
+    let mut app = App::new(view_model, update_rx, animation_config);
 
     // Main event loop
     loop {
@@ -289,10 +776,16 @@ pub fn run_branch_tui<T: BranchStore>(
 
         terminal.draw(|frame| render(frame, &mut app))?;
 
-        if event::poll(Duration::from_millis(app.animation_config.poll_interval_ms))? {
-            if app.handle_event(event::read()?) {
-                break;
-            }
+        // Store operations may wait on GitHub; the frame just drawn shows they are underway
+        if app.pending.is_some() {
+            app.run_pending().await;
+            continue;
+        }
+
+        if event::poll(Duration::from_millis(app.animation_config.poll_interval_ms))?
+            && app.handle_event(event::read()?)
+        {
+            break;
         }
     }
 
@@ -300,3 +793,128 @@ pub fn run_branch_tui<T: BranchStore>(
     ratatui::restore();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryBranchStore;
+    use ratatui::crossterm::event::{KeyEvent, KeyModifiers};
+
+    fn press(app: &mut App<InMemoryBranchStore>, c: char) {
+        app.handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Char(c),
+            KeyModifiers::NONE,
+        )));
+    }
+
+    fn test_app() -> App<InMemoryBranchStore> {
+        let store = InMemoryBranchStore::new(vec![
+            BCBranch::with_pr("feature-open", PrStatus::OPEN, 1, "Open"),
+            BCBranch::with_pr("feature-merged", PrStatus::MERGED, 2, "Merged"),
+        ]);
+        let (_, rx) = tokio::sync::mpsc::unbounded_channel();
+        App::new(BranchViewModel::new(store), rx, AnimationConfig::default())
+    }
+
+    #[tokio::test]
+    async fn delete_requires_confirmation() {
+        let mut app = test_app();
+
+        press(&mut app, 'd');
+
+        // Nothing deleted yet, confirmation popup is open
+        assert!(matches!(app.modal, Some(Modal::ConfirmDelete(_))));
+        assert_eq!(app.view_state.branches.len(), 2);
+
+        press(&mut app, 'y');
+        assert_eq!(app.pending, Some(Pending::Delete));
+        app.run_pending().await;
+
+        assert!(app.modal.is_none());
+        assert_eq!(app.view_state.branches.len(), 1);
+        assert_eq!(app.view_state.branches[0].name, "feature-open");
+        let notification = app.notification.unwrap();
+        assert_eq!(notification.message, "Deleted 1 branch(es)");
+        assert!(!notification.is_error);
+    }
+
+    #[test]
+    fn search_captures_keys_until_enter() {
+        let mut app = test_app();
+
+        press(&mut app, '/');
+        for c in "merged".chars() {
+            press(&mut app, c);
+        }
+
+        // `m`, `d` and `e` were typed into the query, not handled as commands
+        assert!(app.searching);
+        assert!(app.modal.is_none());
+        assert_eq!(app.view_state.filter.query, "merged");
+        assert_eq!(app.view_state.selected_index, 1);
+
+        app.handle_event(Event::Key(KeyEvent::new(
+            KeyCode::Enter,
+            KeyModifiers::NONE,
+        )));
+        press(&mut app, 'o');
+
+        // Filters combine: nothing is both open and matching the query
+        assert!(!app.searching);
+        assert_eq!(app.view_state.filter.status, Some(StatusFilter::Open));
+        assert!(app.view_model.visible_indices(&app.view_state).is_empty());
+        assert_eq!(app.view_state.selected_branches, vec!["feature-merged"]);
+    }
+
+    #[test]
+    fn notification_lists_skipped_and_failed_branches() {
+        let report = vec![
+            DeletionReport {
+                name: "gone".to_owned(),
+                tip_sha: None,
+                reason: "selected manually".to_owned(),
+                outcome: Some(DeletionOutcome::Skipped),
+                remote: false,
+                remote_outcome: None,
+            },
+            DeletionReport {
+                name: "locked".to_owned(),
+                tip_sha: None,
+                reason: "selected manually".to_owned(),
+                outcome: Some(DeletionOutcome::Failed(
+                    std::io::Error::other("disk full").into(),
+                )),
+                remote: false,
+                remote_outcome: None,
+            },
+        ];
+
+        let notification = Notification::from_deletion_report(&report);
+
+        assert!(notification.is_error);
+        assert_eq!(
+            notification.message,
+            "Deleted 0 branch(es) | Skipped (not found): gone | Failed: locked (I/O error: disk full)"
+        );
+    }
+
+    #[test]
+    fn any_other_key_cancels_deletion() {
+        let mut app = test_app();
+
+        press(&mut app, 'd');
+        press(&mut app, 'n');
+
+        assert!(app.modal.is_none());
+        assert_eq!(app.view_state.branches.len(), 2);
+    }
+
+    #[test]
+    fn commit_ages_use_the_largest_unit() {
+        assert_eq!(format_age(30), "just now");
+        assert_eq!(format_age(60), "1 minute ago");
+        assert_eq!(format_age(2 * 24 * 3600 + 5), "2 days ago");
+        assert_eq!(format_age(3 * 7 * 24 * 3600), "3 weeks ago");
+        assert_eq!(format_age(2 * 365 * 24 * 3600), "2 years ago");
+    }
+}
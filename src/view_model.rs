@@ -1,5 +1,12 @@
-use crate::store::BranchStore;
-use crate::{BCBranch, PrStatus};
+use std::fmt;
+use std::sync::Arc;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::error::{BranchCleanerError, Result};
+use crate::policy::{self, SelectionPolicy, SelectionRule};
+use crate::store::{BranchScope, BranchStore, DeletionOutcome, RateLimit};
+use crate::{BCBranch, PrStatus, UpstreamStatus};
 
 /// ViewState represents the pure data state of the TUI
 /// This is a simple data structure with no business logic
@@ -8,23 +15,170 @@ pub struct ViewState {
     pub branches: Vec<BCBranch>,
     pub selected_index: usize,
     pub selected_branches: Vec<String>, // Names of branches marked for deletion
+    pub filter: BranchFilter,
 }
 
 impl ViewState {
     /// Create a new ViewState with the given branches
-    /// By default, selects all merged branches (safe to delete)
-    pub fn new(branches: Vec<BCBranch>) -> Self {
-        let selected_branches = branches
-            .iter()
-            .filter(|b| b.pr_status == PrStatus::MERGED)
-            .map(|b| b.name.clone())
-            .collect();
+    /// Selects the branches the policy matches (by default, the merged ones)
+    pub fn new(branches: Vec<BCBranch>, policy: &dyn SelectionPolicy) -> Self {
+        let selected_branches = policy.select(&branches);
 
         Self {
             branches,
             selected_index: 0,
             selected_branches,
+            filter: BranchFilter::default(),
+        }
+    }
+}
+
+/// Quick filter on the PR status
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatusFilter {
+    /// Merged PRs, including branches merged per git history or with commits after the merge
+    Merged,
+    Open,
+    NoPr,
+}
+
+impl StatusFilter {
+    fn matches(self, status: &PrStatus) -> bool {
+        match self {
+            StatusFilter::Merged => matches!(
+                status,
+                PrStatus::MERGED | PrStatus::MERGED_LOCALLY | PrStatus::MERGED_WITH_CHANGES(_)
+            ),
+            StatusFilter::Open => *status == PrStatus::OPEN,
+            StatusFilter::NoPr => *status == PrStatus::NONE,
+        }
+    }
+}
+
+impl fmt::Display for StatusFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            StatusFilter::Merged => "merged",
+            StatusFilter::Open => "open",
+            StatusFilter::NoPr => "no PR",
+        })
+    }
+}
+
+/// Narrows the branches shown; branches hidden by it stay selected
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BranchFilter {
+    /// Matched against branch names and PR titles, ignoring case: as a regex,
+    /// or as plain text when it is not a valid regex
+    pub query: String,
+    pub status: Option<StatusFilter>,
+}
+
+impl BranchFilter {
+    /// True when some branches may be hidden
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty() || self.status.is_some()
+    }
+
+    /// Indices of the branches passing the filter
+    pub fn visible_indices(&self, branches: &[BCBranch]) -> Vec<usize> {
+        let query = QueryMatcher::new(&self.query);
+        branches
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| {
+                self.status
+                    .is_none_or(|status| status.matches(&b.pr_status))
+                    && query.matches(b)
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// A search query, compiled once per filtering pass
+enum QueryMatcher {
+    All,
+    Regex(Regex),
+    Text(String),
+}
+
+impl QueryMatcher {
+    fn new(query: &str) -> Self {
+        if query.is_empty() {
+            return QueryMatcher::All;
         }
+        match RegexBuilder::new(query).case_insensitive(true).build() {
+            Ok(regex) => QueryMatcher::Regex(regex),
+            Err(_) => QueryMatcher::Text(query.to_lowercase()),
+        }
+    }
+
+    fn matches(&self, branch: &BCBranch) -> bool {
+        let mut fields = std::iter::once(branch.name.as_str()).chain(branch.pr_title.as_deref());
+        match self {
+            QueryMatcher::All => true,
+            QueryMatcher::Regex(regex) => fields.any(|field| regex.is_match(field)),
+            QueryMatcher::Text(text) => fields.any(|field| field.to_lowercase().contains(text)),
+        }
+    }
+}
+
+/// Describes a branch handled by a deletion (or by a dry run of one)
+#[derive(Debug)]
+pub struct DeletionReport {
+    pub name: String,
+    pub tip_sha: Option<String>,
+    /// Why the branch was selected for deletion
+    pub reason: String,
+    /// What the store did with the branch (`None` in dry-run mode)
+    pub outcome: Option<DeletionOutcome>,
+    /// Whether `origin/<name>` is deleted as well
+    pub remote: bool,
+    /// What happened to `origin/<name>` (`None` in dry-run mode or when not requested)
+    pub remote_outcome: Option<DeletionOutcome>,
+}
+
+/// Branches whose PR is open (or not yet known) are never deleted on origin:
+/// that would close the PR
+fn remote_refusal(branch: &BCBranch) -> Option<String> {
+    match (&branch.pr_status, branch.pr_number) {
+        (PrStatus::OPEN, Some(number)) => Some(format!(
+            "{} is the head of open PR #{}",
+            branch.name, number
+        )),
+        (PrStatus::OPEN, None) => Some(format!("{} is the head of an open PR", branch.name)),
+        (PrStatus::LOADING, _) => Some(format!("PR status of {} is still loading", branch.name)),
+        (PrStatus::ERROR(_), _) => {
+            Some(format!("PR status of {} could not be loaded", branch.name))
+        }
+        (PrStatus::UNKNOWN, _) => {
+            Some(format!("PR status of {} is unknown (offline)", branch.name))
+        }
+        _ => None,
+    }
+}
+
+/// Explains why a branch is selected, given the auto-selection rule it matched
+fn selection_reason(branch: &BCBranch, rule: Option<SelectionRule>) -> String {
+    if let Some(
+        rule @ (SelectionRule::Stale { .. } | SelectionRule::Closed { .. } | SelectionRule::All(_)),
+    ) = rule
+    {
+        return rule.to_string();
+    }
+    match (&branch.pr_status, branch.pr_number, &branch.upstream) {
+        (PrStatus::MERGED, Some(number), _) => format!("PR #{} merged", number),
+        (PrStatus::MERGED, None, _) => "PR merged".to_string(),
+        (PrStatus::MERGED_LOCALLY, _, _) => "merged into base branch (git history)".to_string(),
+        (PrStatus::MERGED_WITH_CHANGES(count), _, _) => {
+            format!(
+                "selected manually ({} commit(s) after the merge are lost)",
+                count
+            )
+        }
+        (_, _, Some(UpstreamStatus::Gone { name })) => format!("upstream {} is gone", name),
+        _ => "selected manually".to_string(),
     }
 }
 
@@ -33,38 +187,157 @@ impl ViewState {
 #[derive(Debug, Clone)]
 pub struct BranchViewModel<T: BranchStore> {
     store: T,
+    dry_run: bool,
+    policy: Arc<dyn SelectionPolicy>,
+    delete_remote: bool,
 }
 
 impl<T: BranchStore> BranchViewModel<T> {
     /// Creates a new ViewModel with dependency-injected store
     pub fn new(store: T) -> Self {
-        Self { store }
+        Self {
+            store,
+            dry_run: false,
+            policy: Arc::new(policy::default_policy()),
+            delete_remote: false,
+        }
+    }
+
+    /// Also deletes `origin/<name>` when deleting a branch
+    pub fn with_delete_remote(mut self, delete_remote: bool) -> Self {
+        self.delete_remote = delete_remote;
+        self
+    }
+
+    /// Whether deletions also remove the branch on origin
+    pub fn deletes_remote(&self) -> bool {
+        self.delete_remote
+    }
+
+    /// Switches deleting the branch on origin on or off
+    pub fn toggle_delete_remote(&mut self) {
+        self.delete_remote = !self.delete_remote;
+    }
+
+    /// Sets the policy used to pre-select branches for deletion
+    pub fn with_policy(mut self, policy: Box<dyn SelectionPolicy>) -> Self {
+        self.policy = Arc::from(policy);
+        self
+    }
+
+    /// The policy pre-selecting branches for deletion
+    pub fn policy(&self) -> &dyn SelectionPolicy {
+        self.policy.as_ref()
+    }
+
+    /// Enables dry-run mode: deletions are reported but never reach the store
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Whether deletions are only being previewed
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// The branch other branches are merged into, if the store knows it
+    pub fn base_branch(&self) -> Option<String> {
+        self.store.base_branch()
+    }
+
+    /// Which branches are listed: local ones, or the ones on origin
+    pub fn scope(&self) -> BranchScope {
+        self.store.scope()
+    }
+
+    /// Remaining GitHub API quota, if known
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.store.rate_limit()
+    }
+
+    /// Where the GitHub token came from, if the store uses GitHub
+    pub fn token_source(&self) -> Option<String> {
+        self.store.token_source()
+    }
+
+    /// The auto-selection rule matching a branch, shown next to selected branches
+    pub fn selection_rule(&self, branch: &BCBranch) -> Option<SelectionRule> {
+        self.policy.rule_for(branch)
     }
 
     /// Loads branches from the store and returns ViewState
     pub fn load_initial_state(&self) -> ViewState {
-        ViewState::new(self.store.list_branches())
+        ViewState::new(self.store.list_branches(), self.policy.as_ref())
+    }
+
+    /// Indices into `state.branches` of the branches the filter shows
+    pub fn visible_indices(&self, state: &ViewState) -> Vec<usize> {
+        state.filter.visible_indices(&state.branches)
+    }
+
+    /// Filters the branches by name or PR title (an empty query shows every branch)
+    pub fn set_query(&self, state: &mut ViewState, query: String) {
+        state.filter.query = query;
+        self.keep_cursor_visible(state);
+    }
+
+    /// Shows only branches with the given status, or every branch when already filtered by it
+    pub fn toggle_status_filter(&self, state: &mut ViewState, status: StatusFilter) {
+        state.filter.status = (state.filter.status != Some(status)).then_some(status);
+        self.keep_cursor_visible(state);
     }
 
-    /// Moves selection up (mutates state in place - r3bl pattern)
+    /// Removes the search query and the status filter
+    pub fn clear_filter(&self, state: &mut ViewState) {
+        state.filter = BranchFilter::default();
+    }
+
+    /// Moves the cursor to the first shown branch when its branch got hidden
+    fn keep_cursor_visible(&self, state: &mut ViewState) {
+        if !state.filter.is_active() {
+            return;
+        }
+        let visible = self.visible_indices(state);
+        if !visible.contains(&state.selected_index) {
+            if let Some(&first) = visible.first() {
+                state.selected_index = first;
+            }
+        }
+    }
+
+    /// Whether the cursor is on a branch the filter shows
+    fn cursor_visible(&self, state: &ViewState) -> bool {
+        self.visible_indices(state).contains(&state.selected_index)
+    }
+
+    /// Moves selection up to the previous shown branch (mutates state in place - r3bl pattern)
     pub fn move_up(&self, state: &mut ViewState) {
-        if state.selected_index > 0 {
-            state.selected_index -= 1;
+        if let Some(&previous) = self
+            .visible_indices(state)
+            .iter()
+            .rev()
+            .find(|&&i| i < state.selected_index)
+        {
+            state.selected_index = previous;
         }
     }
 
-    /// Moves selection down (mutates state in place - r3bl pattern)
+    /// Moves selection down to the next shown branch (mutates state in place - r3bl pattern)
     pub fn move_down(&self, state: &mut ViewState) {
-        let max_index = state.branches.len().saturating_sub(1);
-        if state.selected_index < max_index {
-            state.selected_index += 1;
+        if let Some(&next) = self
+            .visible_indices(state)
+            .iter()
+            .find(|&&i| i > state.selected_index)
+        {
+            state.selected_index = next;
         }
     }
 
     /// Toggles selection of the current branch (add if not selected, remove if selected)
     pub fn toggle_selection(&self, state: &mut ViewState) {
-        if state.selected_index >= state.branches.len() {
-            return; // Safety: invalid index
+        if !self.cursor_visible(state) {
+            return; // Safety: invalid index, or every branch is filtered out
         }
 
         let current_branch_name = &state.branches[state.selected_index].name;
@@ -82,47 +355,152 @@ impl<T: BranchStore> BranchViewModel<T> {
         }
     }
 
+    /// Builds a report of the selected branches without deleting anything
+    pub fn preview_deletion(&self, state: &ViewState) -> Vec<DeletionReport> {
+        state
+            .selected_branches
+            .iter()
+            .map(|name| {
+                let branch = state.branches.iter().find(|b| &b.name == name);
+                DeletionReport {
+                    name: name.clone(),
+                    tip_sha: branch.and_then(|b| b.tip_sha.clone()),
+                    reason: branch
+                        .map(|b| selection_reason(b, self.policy.rule_for(b)))
+                        .unwrap_or_else(|| "selected manually".to_string()),
+                    outcome: None,
+                    remote: self.delete_remote,
+                    remote_outcome: None,
+                }
+            })
+            .collect()
+    }
+
     /// Deletes selected branches from the store and updates the state
-    pub fn delete_selected_branches(&mut self, state: &mut ViewState) {
-        // 1. Delete branches from the store
-        self.store.delete_branches(&state.selected_branches);
+    /// Returns what happened to each branch; in dry-run mode the store and state are left untouched
+    pub async fn delete_selected_branches(&mut self, state: &mut ViewState) -> Vec<DeletionReport> {
+        let mut report = self.preview_deletion(state);
+        if self.dry_run {
+            return report;
+        }
 
-        // 2. Get updated branches from store
+        // 1. Delete branches on origin first, while the store still lists them
+        if self.delete_remote {
+            self.delete_remote_branches(state, &mut report);
+        }
+
+        // 2. Delete branches from the store
+        let results = self.store.delete_branches(&state.selected_branches).await;
+        for result in results {
+            if let Some(entry) = report.iter_mut().find(|e| e.name == result.name) {
+                entry.outcome = Some(result.outcome);
+            }
+        }
+
+        // 3. Get updated branches from store
         let new_branches = self.store.list_branches();
 
-        // 3. Select all merged branches in the new list (default selection)
-        let new_selected = new_branches
-            .iter()
-            .filter(|b| b.pr_status == PrStatus::MERGED)
-            .map(|b| b.name.clone())
-            .collect();
+        // 4. Select the branches the policy matches in the new list
+        let new_selected = self.policy.select(&new_branches);
 
-        // 4. Update state with new branches and selection
+        // 5. Update state with new branches and selection
         state.branches = new_branches;
         state.selected_branches = new_selected;
         state.selected_index = 0; // Reset to beginning after deletion
+        self.keep_cursor_visible(state);
+
+        report
+    }
+
+    /// Deletes the reported branches on origin, refusing heads of open PRs
+    fn delete_remote_branches(&mut self, state: &ViewState, report: &mut [DeletionReport]) {
+        let mut to_delete = Vec::new();
+        for entry in report.iter_mut() {
+            let refusal = state
+                .branches
+                .iter()
+                .find(|b| b.name == entry.name)
+                .and_then(remote_refusal);
+            match refusal {
+                Some(reason) => {
+                    entry.remote_outcome = Some(DeletionOutcome::Failed(
+                        BranchCleanerError::RemoteDeleteRefused(reason),
+                    ))
+                }
+                None => to_delete.push(entry.name.clone()),
+            }
+        }
+
+        for result in self.store.delete_remote_branches(&to_delete) {
+            if let Some(entry) = report.iter_mut().find(|e| e.name == result.name) {
+                entry.remote_outcome = Some(result.outcome);
+            }
+        }
+    }
+
+    /// Restores the most recently deleted branches and reloads the state
+    /// The current selection is kept; restored branches are not re-selected
+    pub async fn restore_deleted_branches(&mut self, state: &mut ViewState) -> Result<Vec<String>> {
+        if self.dry_run {
+            return Ok(Vec::new()); // Nothing was deleted
+        }
+
+        // Reload even after a failure: some branches may be back
+        let restored = self.store.restore_deleted_branches().await;
+
+        state.branches = self.store.list_branches();
+        state
+            .selected_branches
+            .retain(|name| state.branches.iter().any(|b| &b.name == name));
+        state.selected_index = state
+            .selected_index
+            .min(state.branches.len().saturating_sub(1));
+        self.keep_cursor_visible(state);
+
+        restored
+    }
+
+    /// Looks up the PR of the branch under the cursor again if its lookup failed
+    /// Returns the refreshed branch, or `None` when there was nothing to retry
+    pub async fn retry_lookup(&mut self, state: &mut ViewState) -> Option<BCBranch> {
+        if !self.cursor_visible(state) {
+            return None;
+        }
+        let current = state.branches.get(state.selected_index)?;
+        if !matches!(current.pr_status, PrStatus::ERROR(_)) {
+            return None;
+        }
+
+        let refreshed = self.store.retry_pr_lookup(&current.name).await?;
+        self.update_branch(state, refreshed.clone());
+        Some(refreshed)
     }
 
     /// Updates a single branch in the state (for streaming updates)
     /// Finds the branch by name and replaces it with the updated version
-    /// Auto-selects merged branches when they transition from LOADING
+    /// Auto-selects branches the policy matches when they transition from LOADING (or a failed lookup)
     pub fn update_branch(&self, state: &mut ViewState, updated_branch: BCBranch) {
         if let Some(pos) = state
             .branches
             .iter()
             .position(|b| b.name == updated_branch.name)
         {
-            let was_loading = state.branches[pos].pr_status == PrStatus::LOADING;
-            let is_now_merged = updated_branch.pr_status == PrStatus::MERGED;
-
-            // Auto-select merged branches when they transition from LOADING
-            if was_loading && is_now_merged {
-                if !state.selected_branches.contains(&updated_branch.name) {
-                    state.selected_branches.push(updated_branch.name.clone());
-                }
+            let was_loading = matches!(
+                state.branches[pos].pr_status,
+                PrStatus::LOADING | PrStatus::ERROR(_)
+            );
+            let is_now_matched = self.policy.matches(&updated_branch);
+
+            // Auto-select branches the policy matches once they transition from LOADING
+            if was_loading
+                && is_now_matched
+                && !state.selected_branches.contains(&updated_branch.name)
+            {
+                state.selected_branches.push(updated_branch.name.clone());
             }
 
             state.branches[pos] = updated_branch;
+            self.keep_cursor_visible(state);
         }
     }
 }
@@ -130,7 +508,9 @@ impl<T: BranchStore> BranchViewModel<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::policy::SECONDS_PER_DAY;
     use crate::store::InMemoryBranchStore;
+    use crate::{CommitInfo, PrSummary};
 
     fn create_test_branches() -> Vec<BCBranch> {
         vec![
@@ -143,12 +523,13 @@ mod tests {
     #[test]
     fn can_create_view_state_with_branches() {
         let branches = create_test_branches();
-        let state = ViewState::new(branches.clone());
+        let state = ViewState::new(branches.clone(), &policy::default_policy());
 
         let expected_state = ViewState {
             branches: branches.clone(),
             selected_index: 0,
             selected_branches: vec!["feature-2".to_owned()], // Only merged branch
+            filter: BranchFilter::default(),
         };
 
         assert_eq!(state, expected_state);
@@ -169,6 +550,7 @@ mod tests {
             branches: test_branches,
             selected_index: 0,
             selected_branches: vec!["feature-2".to_owned()], // Only merged branch
+            filter: BranchFilter::default(),
         };
 
         assert_eq!(view_state, expected_state);
@@ -178,7 +560,7 @@ mod tests {
     fn move_down_increments_selected_index() {
         // Arrange
         let branches = create_test_branches();
-        let mut state = ViewState::new(branches.clone());
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
         let store = InMemoryBranchStore::new(branches.clone());
         let view_model = BranchViewModel::new(store);
 
@@ -190,6 +572,7 @@ mod tests {
             branches: branches.clone(),
             selected_index: 1,
             selected_branches: vec!["feature-2".to_owned()], // Selection unchanged
+            filter: BranchFilter::default(),
         };
 
         assert_eq!(state, expected_state);
@@ -199,7 +582,7 @@ mod tests {
     fn move_up_decrements_selected_index() {
         // Arrange
         let branches = create_test_branches();
-        let mut state = ViewState::new(branches.clone());
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
         let store = InMemoryBranchStore::new(branches.clone());
         let view_model = BranchViewModel::new(store);
 
@@ -213,6 +596,7 @@ mod tests {
             branches: branches.clone(),
             selected_index: 1,
             selected_branches: vec!["feature-2".to_owned()], // Selection unchanged
+            filter: BranchFilter::default(),
         };
 
         assert_eq!(state, expected_state);
@@ -222,7 +606,7 @@ mod tests {
     fn toggle_selection_adds_unselected_branch() {
         // Arrange: State at index 0 (main branch, not pre-selected)
         let branches = create_test_branches();
-        let mut state = ViewState::new(branches.clone());
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
         let store = InMemoryBranchStore::new(branches.clone());
         let view_model = BranchViewModel::new(store);
 
@@ -234,6 +618,7 @@ mod tests {
             branches: branches.clone(),
             selected_index: 0,
             selected_branches: vec!["feature-2".to_owned(), "main".to_owned()],
+            filter: BranchFilter::default(),
         };
 
         assert_eq!(state, expected_state);
@@ -243,7 +628,7 @@ mod tests {
     fn toggle_selection_removes_selected_branch() {
         // Arrange: Move to feature-2 (index 2, already selected)
         let branches = create_test_branches();
-        let mut state = ViewState::new(branches.clone());
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
         let store = InMemoryBranchStore::new(branches.clone());
         let view_model = BranchViewModel::new(store);
         view_model.move_down(&mut state);
@@ -257,21 +642,22 @@ mod tests {
             branches: branches.clone(),
             selected_index: 2,
             selected_branches: vec![], // Empty - feature-2 removed
+            filter: BranchFilter::default(),
         };
 
         assert_eq!(state, expected_state);
     }
 
-    #[test]
-    fn delete_selected_branches_removes_them_and_reloads_state() {
+    #[tokio::test]
+    async fn delete_selected_branches_removes_them_and_reloads_state() {
         // Arrange: State with feature-2 selected (merged)
         let branches = create_test_branches();
-        let mut state = ViewState::new(branches.clone());
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
         let store = InMemoryBranchStore::new(branches.clone());
         let mut view_model = BranchViewModel::new(store);
 
         // Act: Delete selected branches
-        view_model.delete_selected_branches(&mut state);
+        view_model.delete_selected_branches(&mut state).await;
 
         // Assert: feature-2 is deleted, state reloaded with remaining branches
         let expected_branches = vec![
@@ -281,13 +667,86 @@ mod tests {
 
         let expected_state = ViewState {
             branches: expected_branches,
-            selected_index: 0, // Reset to 0
+            selected_index: 0,         // Reset to 0
             selected_branches: vec![], // No merged branches remain
+            filter: BranchFilter::default(),
         };
 
         assert_eq!(state, expected_state);
     }
 
+    #[tokio::test]
+    async fn delete_selected_branches_reports_outcome_per_branch() {
+        // Arrange: feature-2 selected, plus a branch that no longer exists
+        let branches = create_test_branches();
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
+        state.selected_branches.push("gone".to_owned());
+        let store = InMemoryBranchStore::new(branches);
+        let mut view_model = BranchViewModel::new(store);
+
+        // Act
+        let report = view_model.delete_selected_branches(&mut state).await;
+
+        // Assert
+        assert_eq!(report.len(), 2);
+        assert!(matches!(report[0].outcome, Some(DeletionOutcome::Deleted)));
+        assert!(matches!(report[1].outcome, Some(DeletionOutcome::Skipped)));
+    }
+
+    #[tokio::test]
+    async fn dry_run_reports_selection_without_deleting() {
+        // Arrange: feature-2 auto-selected, main selected manually
+        let branches = vec![
+            BCBranch::new("main", PrStatus::NONE),
+            BCBranch::with_pr("feature-2", PrStatus::MERGED, 2, "Feature 2")
+                .with_tip_sha(Some("abc1234def".to_owned())),
+        ];
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
+        let store = InMemoryBranchStore::new(branches.clone());
+        let mut view_model = BranchViewModel::new(store).with_dry_run(true);
+        view_model.toggle_selection(&mut state);
+
+        // Act
+        let report = view_model.delete_selected_branches(&mut state).await;
+
+        // Assert: Report explains each branch, nothing was deleted
+        let summary: Vec<(&str, Option<&str>, &str)> = report
+            .iter()
+            .map(|e| (e.name.as_str(), e.tip_sha.as_deref(), e.reason.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("feature-2", Some("abc1234def"), "PR #2 merged"),
+                ("main", None, "selected manually"),
+            ]
+        );
+        assert!(report.iter().all(|e| e.outcome.is_none()));
+        assert_eq!(state.branches, branches);
+        assert_eq!(view_model.load_initial_state().branches, branches);
+    }
+
+    #[tokio::test]
+    async fn restore_brings_back_deleted_branches_without_selecting_them() {
+        // Arrange: Delete the merged branch
+        let branches = create_test_branches();
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
+        let store = InMemoryBranchStore::new(branches.clone());
+        let mut view_model = BranchViewModel::new(store);
+        view_model.delete_selected_branches(&mut state).await;
+
+        // Act: Undo
+        let restored = view_model
+            .restore_deleted_branches(&mut state)
+            .await
+            .unwrap();
+
+        // Assert: Branch is back but not re-selected
+        assert_eq!(restored, vec!["feature-2".to_owned()]);
+        assert_eq!(state.branches, branches);
+        assert!(state.selected_branches.is_empty());
+    }
+
     #[test]
     fn update_branch_replaces_loading_branch_with_enriched_data() {
         // Arrange: State with branches in LOADING status
@@ -295,7 +754,7 @@ mod tests {
             BCBranch::new("main", PrStatus::LOADING),
             BCBranch::new("feature-1", PrStatus::LOADING),
         ];
-        let mut state = ViewState::new(loading_branches.clone());
+        let mut state = ViewState::new(loading_branches.clone(), &policy::default_policy());
         let store = InMemoryBranchStore::new(loading_branches);
         let view_model = BranchViewModel::new(store);
 
@@ -315,7 +774,7 @@ mod tests {
             BCBranch::new("main", PrStatus::LOADING),
             BCBranch::new("feature-merged", PrStatus::LOADING),
         ];
-        let mut state = ViewState::new(loading_branches.clone());
+        let mut state = ViewState::new(loading_branches.clone(), &policy::default_policy());
         // LOADING branches are not auto-selected
         assert!(state.selected_branches.is_empty());
 
@@ -334,7 +793,7 @@ mod tests {
     fn update_branch_does_not_select_non_merged_branches() {
         // Arrange: State with branches in LOADING status
         let loading_branches = vec![BCBranch::new("feature-open", PrStatus::LOADING)];
-        let mut state = ViewState::new(loading_branches.clone());
+        let mut state = ViewState::new(loading_branches.clone(), &policy::default_policy());
         let store = InMemoryBranchStore::new(loading_branches);
         let view_model = BranchViewModel::new(store);
 
@@ -346,11 +805,40 @@ mod tests {
         assert!(state.selected_branches.is_empty());
     }
 
+    #[tokio::test]
+    async fn failed_lookups_are_not_selected_until_a_retry_succeeds() {
+        // Arrange: The lookup for feature-merged failed; the store now knows its PR
+        let failed = BCBranch::new("feature-merged", PrStatus::ERROR("timed out".to_owned()));
+        let mut state = ViewState::new(
+            vec![BCBranch::new("feature-merged", PrStatus::LOADING)],
+            &policy::default_policy(),
+        );
+        let store = InMemoryBranchStore::new(vec![BCBranch::with_pr(
+            "feature-merged",
+            PrStatus::MERGED,
+            10,
+            "Merged PR",
+        )]);
+        let mut view_model = BranchViewModel::new(store);
+        view_model.update_branch(&mut state, failed);
+        assert!(state.selected_branches.is_empty());
+
+        // Act
+        let refreshed = view_model.retry_lookup(&mut state).await;
+
+        // Assert: Merged after the retry, so it is auto-selected now
+        assert_eq!(refreshed.map(|b| b.pr_status), Some(PrStatus::MERGED));
+        assert_eq!(state.branches[0].pr_status, PrStatus::MERGED);
+        assert_eq!(state.selected_branches, vec!["feature-merged".to_owned()]);
+        // Nothing left to retry
+        assert_eq!(view_model.retry_lookup(&mut state).await, None);
+    }
+
     #[test]
     fn update_branch_ignores_unknown_branches() {
         // Arrange: State with known branches
         let branches = vec![BCBranch::new("main", PrStatus::LOADING)];
-        let mut state = ViewState::new(branches.clone());
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
         let store = InMemoryBranchStore::new(branches);
         let view_model = BranchViewModel::new(store);
 
@@ -363,6 +851,91 @@ mod tests {
         assert_eq!(state.branches[0].name, "main");
     }
 
+    #[test]
+    fn locally_merged_branches_are_auto_selected() {
+        let branches = vec![
+            BCBranch::new("experiment", PrStatus::NONE),
+            BCBranch::new("pushed-directly", PrStatus::MERGED_LOCALLY),
+        ];
+
+        let state = ViewState::new(branches, &policy::default_policy());
+
+        assert_eq!(state.selected_branches, vec!["pushed-directly".to_owned()]);
+    }
+
+    #[test]
+    fn gone_upstream_branches_are_selected_only_when_enabled() {
+        // Arrange
+        let gone = Some(UpstreamStatus::Gone {
+            name: "origin/old".to_owned(),
+        });
+        let branches = vec![
+            BCBranch::new("old", PrStatus::NONE).with_upstream(gone.clone()),
+            BCBranch::with_pr("in-review", PrStatus::OPEN, 7, "WIP").with_upstream(gone),
+            BCBranch::new("local-only", PrStatus::NONE),
+        ];
+        let store = InMemoryBranchStore::new(branches.clone());
+        let view_model = BranchViewModel::new(store)
+            .with_policy(Box::new(policy::from_rules(true, true, None, None)));
+
+        // Act
+        let default_state = ViewState::new(branches, &policy::default_policy());
+        let state = view_model.load_initial_state();
+
+        // Assert: open PRs are never selected for a gone upstream
+        assert!(default_state.selected_branches.is_empty());
+        assert_eq!(state.selected_branches, vec!["old".to_owned()]);
+        let report = view_model.preview_deletion(&state);
+        assert_eq!(report[0].reason, "upstream origin/old is gone");
+    }
+
+    #[tokio::test]
+    async fn delete_remote_refuses_open_pr_heads() {
+        // Arrange: Both branches track origin; one is the head of an open PR
+        let tracking = |name: &str| {
+            Some(UpstreamStatus::Tracking {
+                name: format!("origin/{}", name),
+                ahead: 0,
+                behind: 0,
+            })
+        };
+        let branches = vec![
+            BCBranch::with_pr("done", PrStatus::MERGED, 1, "Done").with_upstream(tracking("done")),
+            BCBranch::with_pr("wip", PrStatus::OPEN, 2, "WIP").with_upstream(tracking("wip")),
+            BCBranch::new("never-pushed", PrStatus::NONE),
+        ];
+        let store = InMemoryBranchStore::new(branches);
+        let mut view_model = BranchViewModel::new(store).with_delete_remote(true);
+        let mut state = view_model.load_initial_state();
+        state.selected_branches = vec![
+            "done".to_owned(),
+            "wip".to_owned(),
+            "never-pushed".to_owned(),
+        ];
+
+        // Act
+        let report = view_model.delete_selected_branches(&mut state).await;
+
+        // Assert: Local deletion still happens; only the open PR head is refused on origin
+        assert!(report
+            .iter()
+            .all(|e| e.remote && matches!(e.outcome, Some(DeletionOutcome::Deleted))));
+        assert!(matches!(
+            report[0].remote_outcome,
+            Some(DeletionOutcome::Deleted)
+        ));
+        assert!(matches!(
+            report[1].remote_outcome,
+            Some(DeletionOutcome::Failed(
+                BranchCleanerError::RemoteDeleteRefused(_)
+            ))
+        ));
+        assert!(matches!(
+            report[2].remote_outcome,
+            Some(DeletionOutcome::Skipped)
+        ));
+    }
+
     #[test]
     fn closed_branches_are_not_auto_selected() {
         // Arrange: Branches including one with CLOSED status (PR closed without merging)
@@ -373,9 +946,169 @@ mod tests {
         ];
 
         // Act: Create ViewState
-        let state = ViewState::new(branches.clone());
+        let state = ViewState::new(branches.clone(), &policy::default_policy());
 
         // Assert: Only MERGED branch is auto-selected, CLOSED is not
         assert_eq!(state.selected_branches, vec!["feature-merged".to_owned()]);
     }
+
+    #[test]
+    fn merged_branches_with_later_commits_are_not_auto_selected() {
+        // Arrange: Two commits were made after the PR merged, and the upstream is gone
+        let gone = UpstreamStatus::Gone {
+            name: "origin/reused".to_owned(),
+        };
+        let status = PrStatus::MERGED_WITH_CHANGES(2);
+        let branches =
+            vec![BCBranch::with_pr("reused", status, 5, "Merged PR").with_upstream(Some(gone))];
+        let rules = policy::from_rules(true, true, None, None);
+
+        // Act
+        let state = ViewState::new(branches.clone(), &policy::default_policy());
+
+        // Assert: Neither the merged rule nor the gone rule picks it up
+        assert!(state.selected_branches.is_empty());
+        assert!(!rules.matches(&branches[0]));
+        assert_eq!(
+            selection_reason(&branches[0], rules.rule_for(&branches[0])),
+            "selected manually (2 commit(s) after the merge are lost)"
+        );
+    }
+
+    #[test]
+    fn age_rules_select_stale_branches_and_long_closed_prs() {
+        // Arrange: "now" is day 100; rules select after 30 days without commits
+        // and 10 days after a PR was closed
+        let now = 100 * SECONDS_PER_DAY;
+        let committed_on_day = |day: i64| {
+            Some(CommitInfo {
+                time: day * SECONDS_PER_DAY,
+                author_name: "Test".to_owned(),
+                author_email: "test@example.com".to_owned(),
+                subject: "work".to_owned(),
+            })
+        };
+        let closed_on = |date: &str| {
+            BCBranch::new("closed", PrStatus::LOADING).with_prs(vec![PrSummary {
+                number: 3,
+                title: "Abandoned".to_owned(),
+                status: PrStatus::CLOSED,
+                closed_at: Some(date.to_owned()),
+                head_sha: None,
+            }])
+        };
+        let rules = policy::from_rules(true, false, Some(30), Some(10));
+
+        let old = BCBranch::new("old", PrStatus::NONE).with_last_commit(committed_on_day(40));
+        let recent = BCBranch::new("recent", PrStatus::NONE).with_last_commit(committed_on_day(90));
+        let old_but_open = BCBranch::with_pr("wip", PrStatus::OPEN, 1, "WIP")
+            .with_last_commit(committed_on_day(1));
+        // Day 80 and day 95 of 1970
+        let closed_long_ago = closed_on("1970-03-22T00:00:00Z");
+        let closed_recently = closed_on("1970-04-06T00:00:00Z");
+
+        // Act + Assert
+        assert_eq!(
+            rules.rule_at(&old, now),
+            Some(SelectionRule::Stale { days: 60 })
+        );
+        assert_eq!(rules.rule_at(&recent, now), None);
+        assert_eq!(rules.rule_at(&old_but_open, now), None);
+        assert_eq!(
+            rules.rule_at(&closed_long_ago, now),
+            Some(SelectionRule::Closed { days: Some(20) })
+        );
+        assert_eq!(rules.rule_at(&closed_recently, now), None);
+        assert_eq!(policy::default_policy().rule_at(&old, now), None);
+        assert_eq!(
+            selection_reason(&old, rules.rule_at(&old, now)),
+            "no commits in 60 days"
+        );
+    }
+
+    #[tokio::test]
+    async fn the_policy_decides_every_selection() {
+        // Arrange: A team that only cleans up closed PRs
+        let branches = vec![
+            BCBranch::with_pr("merged", PrStatus::MERGED, 1, "Merged"),
+            BCBranch::with_pr("abandoned", PrStatus::CLOSED, 2, "Abandoned"),
+            BCBranch::new("loading", PrStatus::LOADING),
+        ];
+        let store = InMemoryBranchStore::new(branches);
+        let mut view_model =
+            BranchViewModel::new(store).with_policy(policy::parse("closed").unwrap());
+
+        // Act + Assert: Initial load, streamed updates and the reload after a deletion
+        let mut state = view_model.load_initial_state();
+        assert_eq!(state.selected_branches, vec!["abandoned".to_owned()]);
+
+        let closed = BCBranch::with_pr("loading", PrStatus::CLOSED, 3, "Also abandoned");
+        view_model.update_branch(&mut state, closed);
+        assert_eq!(
+            state.selected_branches,
+            vec!["abandoned".to_owned(), "loading".to_owned()]
+        );
+        assert_eq!(view_model.preview_deletion(&state)[0].reason, "PR closed");
+
+        state.selected_branches = vec!["merged".to_owned()];
+        view_model.delete_selected_branches(&mut state).await;
+        assert_eq!(state.selected_branches, vec!["abandoned".to_owned()]);
+    }
+
+    #[test]
+    fn filters_hide_branches_but_keep_their_selection() {
+        // Arrange: Cursor on main, the merged branch auto-selected
+        let branches = vec![
+            BCBranch::new("main", PrStatus::NONE),
+            BCBranch::with_pr("fix/login", PrStatus::OPEN, 1, "Fix login"),
+            BCBranch::with_pr("feature-2", PrStatus::MERGED, 2, "Add search"),
+            BCBranch::new("fix-typo", PrStatus::NONE),
+        ];
+        let mut state = ViewState::new(branches.clone(), &policy::default_policy());
+        let store = InMemoryBranchStore::new(branches);
+        let view_model = BranchViewModel::new(store);
+        let visible_names = |state: &ViewState| -> Vec<String> {
+            view_model
+                .visible_indices(state)
+                .iter()
+                .map(|&i| state.branches[i].name.clone())
+                .collect()
+        };
+
+        // Act + Assert: Substring on names and titles, ignoring case
+        view_model.set_query(&mut state, "SEARCH".to_owned());
+        assert_eq!(visible_names(&state), vec!["feature-2"]);
+        assert_eq!(state.selected_index, 2); // Cursor moved off the hidden main
+
+        // Regex, combined with a status filter
+        view_model.set_query(&mut state, "^fix".to_owned());
+        assert_eq!(visible_names(&state), vec!["fix/login", "fix-typo"]);
+        view_model.toggle_status_filter(&mut state, StatusFilter::NoPr);
+        assert_eq!(visible_names(&state), vec!["fix-typo"]);
+
+        // Invalid regex falls back to plain text
+        view_model.set_query(&mut state, "fix(".to_owned());
+        assert!(visible_names(&state).is_empty());
+        view_model.toggle_selection(&mut state); // Nothing shown: nothing toggled
+
+        // Movement skips hidden branches
+        view_model.clear_filter(&mut state);
+        view_model.toggle_status_filter(&mut state, StatusFilter::NoPr);
+        state.selected_index = 0;
+        view_model.move_down(&mut state);
+        assert_eq!(state.selected_index, 3);
+        view_model.move_down(&mut state);
+        assert_eq!(state.selected_index, 3);
+
+        // Selections made under one filter survive the others
+        view_model.toggle_selection(&mut state);
+        view_model.toggle_status_filter(&mut state, StatusFilter::Merged);
+        assert_eq!(visible_names(&state), vec!["feature-2"]);
+        view_model.toggle_status_filter(&mut state, StatusFilter::Merged);
+        assert_eq!(visible_names(&state).len(), 4);
+        assert_eq!(
+            state.selected_branches,
+            vec!["feature-2".to_owned(), "fix-typo".to_owned()]
+        );
+    }
 }